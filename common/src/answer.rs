@@ -0,0 +1,157 @@
+use std::fmt::{self, Display};
+
+use color_eyre::{eyre::anyhow, Result};
+
+/// The answer to one part of a puzzle.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Answer {
+    I64(i64),
+    U64(u64),
+    I128(i128),
+    U128(u128),
+    String(String),
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::I64(n) => write!(f, "{n}"),
+            Answer::U64(n) => write!(f, "{n}"),
+            Answer::I128(n) => write!(f, "{n}"),
+            Answer::U128(n) => write!(f, "{n}"),
+            Answer::String(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+macro_rules! impl_from {
+    ($($t:ty => $variant:ident),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(value: $t) -> Self {
+                    Answer::$variant(value.into())
+                }
+            }
+        )*
+    };
+}
+
+impl_from!(
+    i32 => I64, i64 => I64, u32 => U64, u64 => U64,
+    i128 => I128, u128 => U128, String => String, &str => String
+);
+
+impl From<usize> for Answer {
+    fn from(value: usize) -> Self {
+        Answer::U64(value as u64)
+    }
+}
+
+/// Numbers compare by value whatever their variant, so tests can compare
+/// against plain literals.
+impl PartialEq<i64> for Answer {
+    fn eq(&self, other: &i64) -> bool {
+        let other = *other as i128;
+        match self {
+            Answer::I64(n) => *n as i128 == other,
+            Answer::U64(n) => *n as i128 == other,
+            Answer::I128(n) => *n == other,
+            Answer::U128(n) => i128::try_from(*n) == Ok(other),
+            Answer::String(_) => false,
+        }
+    }
+}
+
+impl PartialEq<&str> for Answer {
+    fn eq(&self, other: &&str) -> bool {
+        matches!(self, Answer::String(s) if s == other)
+    }
+}
+
+/// Integers whose arithmetic reports overflow as an error instead of
+/// wrapping around.
+pub trait Checked: Copy + Display {
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn try_add(self, rhs: Self) -> Result<Self>;
+    fn try_sub(self, rhs: Self) -> Result<Self>;
+    fn try_mul(self, rhs: Self) -> Result<Self>;
+}
+
+macro_rules! impl_checked {
+    ($($t:ty),*) => {
+        $(
+            impl Checked for $t {
+                const ZERO: Self = 0;
+                const ONE: Self = 1;
+
+                fn try_add(self, rhs: Self) -> Result<Self> {
+                    self.checked_add(rhs)
+                        .ok_or_else(|| anyhow!("Overflow computing {self} + {rhs}"))
+                }
+
+                fn try_sub(self, rhs: Self) -> Result<Self> {
+                    self.checked_sub(rhs)
+                        .ok_or_else(|| anyhow!("Overflow computing {self} - {rhs}"))
+                }
+
+                fn try_mul(self, rhs: Self) -> Result<Self> {
+                    self.checked_mul(rhs)
+                        .ok_or_else(|| anyhow!("Overflow computing {self} * {rhs}"))
+                }
+            }
+        )*
+    };
+}
+
+impl_checked!(i32, i64, i128, u32, u64, u128, usize);
+
+/// Sums and products of iterators that fail on overflow.
+pub trait CheckedIterator: Iterator {
+    fn try_sum(mut self) -> Result<Self::Item>
+    where
+        Self: Sized,
+        Self::Item: Checked,
+    {
+        self.try_fold(Self::Item::ZERO, |acc, n| acc.try_add(n))
+    }
+
+    fn try_product(mut self) -> Result<Self::Item>
+    where
+        Self: Sized,
+        Self::Item: Checked,
+    {
+        self.try_fold(Self::Item::ONE, |acc, n| acc.try_mul(n))
+    }
+}
+
+impl<I: Iterator> CheckedIterator for I {}
+
+#[cfg(test)]
+mod test {
+    use super::{Answer, Checked, CheckedIterator};
+
+    #[test]
+    fn test_compare_and_display() {
+        assert_eq!(Answer::from(42), 42);
+        assert_eq!(
+            Answer::from(u64::MAX - 1).to_string(),
+            "18446744073709551614"
+        );
+        assert_ne!(Answer::from(u128::MAX), -1);
+        assert_eq!(Answer::from(-7i128), -7);
+        assert_eq!(Answer::from("abc"), "abc");
+        assert_ne!(Answer::from("1"), 1);
+    }
+
+    #[test]
+    fn test_checked() {
+        assert_eq!([1, 2, 3].into_iter().try_sum().unwrap(), 6);
+        assert_eq!([2u64, 3, 4].into_iter().try_product().unwrap(), 24);
+        assert!([i32::MAX, 1].into_iter().try_sum().is_err());
+        assert!([u64::MAX, 2].into_iter().try_product().is_err());
+        assert!(0u32.try_sub(1).is_err());
+        assert_eq!(Vec::<i64>::new().into_iter().try_product().unwrap(), 1);
+    }
+}
@@ -0,0 +1,101 @@
+/// A map that spreads its entries over a fixed number of buckets with a
+/// caller supplied bucket function and keeps every bucket in insertion
+/// order. Replacing a value keeps its position, removing an entry moves the
+/// ones behind it forward.
+#[derive(Debug, Clone)]
+pub struct BucketMap<K, V> {
+    buckets: Vec<Vec<(K, V)>>,
+    bucket_of: fn(&K) -> usize,
+    len: usize,
+}
+
+impl<K: Eq, V> BucketMap<K, V> {
+    /// `bucket_of` has to return indices below `buckets`
+    pub fn new(buckets: usize, bucket_of: fn(&K) -> usize) -> Self {
+        Self {
+            buckets: (0..buckets).map(|_| vec![]).collect(),
+            bucket_of,
+            len: 0,
+        }
+    }
+
+    fn find(&self, key: &K) -> (usize, Option<usize>) {
+        let bucket = (self.bucket_of)(key);
+        let position = self.buckets[bucket].iter().position(|(k, _)| k == key);
+        (bucket, position)
+    }
+
+    /// Returns the replaced value, if any
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.find(&key) {
+            (bucket, Some(position)) => Some(std::mem::replace(
+                &mut self.buckets[bucket][position].1,
+                value,
+            )),
+            (bucket, None) => {
+                self.buckets[bucket].push((key, value));
+                self.len += 1;
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let (bucket, position) = self.find(key);
+        let (_, value) = self.buckets[bucket].remove(position?);
+        self.len -= 1;
+        Some(value)
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let (bucket, position) = self.find(key);
+        position.map(|p| &self.buckets[bucket][p].1)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.find(key).1.is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The entries of every bucket in order, empty buckets included
+    pub fn buckets(&self) -> impl Iterator<Item = &[(K, V)]> + '_ {
+        self.buckets.iter().map(Vec::as_slice)
+    }
+
+    /// Entries bucket by bucket, each bucket in insertion order
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        self.buckets.iter().flatten().map(|(k, v)| (k, v))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::BucketMap;
+
+    #[test]
+    fn test_bucket_map() {
+        let mut map = BucketMap::new(2, |k: &u32| (k % 2) as usize);
+        assert_eq!(map.insert(3, "a"), None);
+        assert_eq!(map.insert(1, "b"), None);
+        assert_eq!(map.insert(2, "c"), None);
+        assert_eq!(map.insert(5, "d"), None);
+        assert_eq!(map.insert(3, "e"), Some("a"));
+        assert_eq!(map.len(), 4);
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            [(&2, &"c"), (&3, &"e"), (&1, &"b"), (&5, &"d")]
+        );
+        assert_eq!(map.remove(&1), Some("b"));
+        assert_eq!(map.remove(&1), None);
+        assert_eq!(map.get(&5), Some(&"d"));
+        assert!(!map.contains_key(&1));
+        assert_eq!(map.buckets().map(|b| b.len()).collect::<Vec<_>>(), [1, 2]);
+    }
+}
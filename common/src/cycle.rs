@@ -0,0 +1,103 @@
+use std::{collections::HashMap, hash::Hash};
+
+/// A sequence of states that eventually repeats: after `prefix_len` states
+/// the same `period` states repeat forever.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle<S> {
+    pub prefix_len: usize,
+    pub period: usize,
+    /// Every state seen before the first repetition
+    states: Vec<S>,
+}
+
+impl<S> Cycle<S> {
+    /// The state after `step` steps, the initial state being step 0.
+    pub fn state_at(&self, step: usize) -> &S {
+        if step < self.states.len() {
+            &self.states[step]
+        } else {
+            &self.states[self.prefix_len + (step - self.prefix_len) % self.period]
+        }
+    }
+
+    /// Maps `step` to the earliest step with the same state.
+    pub fn canonical_step(&self, step: usize) -> usize {
+        if step < self.prefix_len {
+            step
+        } else {
+            self.prefix_len + (step - self.prefix_len) % self.period
+        }
+    }
+
+    pub fn states(&self) -> &[S] {
+        &self.states
+    }
+}
+
+/// Finds the first repeated state of `states` by hashing every state seen.
+/// Returns `None` if the iterator ends before any state repeats.
+pub fn find_cycle<S, I>(states: I) -> Option<Cycle<S>>
+where
+    S: Hash + Eq + Clone,
+    I: IntoIterator<Item = S>,
+{
+    let mut seen = HashMap::new();
+    let mut history = vec![];
+    for (step, state) in states.into_iter().enumerate() {
+        if let Some(&first) = seen.get(&state) {
+            return Some(Cycle {
+                prefix_len: first,
+                period: step - first,
+                states: history,
+            });
+        }
+        seen.insert(state.clone(), step);
+        history.push(state);
+    }
+    None
+}
+
+/// Finds the cycle of the sequence `initial, next(initial), next(next(initial)), ...`.
+pub fn find_cycle_from<S, F>(initial: S, mut next: F) -> Cycle<S>
+where
+    S: Hash + Eq + Clone,
+    F: FnMut(&S) -> S,
+{
+    find_cycle(std::iter::successors(Some(initial), |s| Some(next(s))))
+        .expect("an infinite sequence over finitely many states always repeats")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{find_cycle, find_cycle_from};
+
+    #[test]
+    fn test_find_cycle() {
+        // 0 1 2 3 4 2 3 4 ...
+        let cycle = find_cycle_from(0, |&n| if n == 4 { 2 } else { n + 1 });
+        assert_eq!(cycle.prefix_len, 2);
+        assert_eq!(cycle.period, 3);
+        assert_eq!(*cycle.state_at(3), 3);
+        assert_eq!(*cycle.state_at(5), 2);
+        assert_eq!(*cycle.state_at(1_000_000_000), 2 + (1_000_000_000 - 2) % 3);
+        assert_eq!(cycle.canonical_step(8), 2);
+    }
+
+    #[test]
+    fn test_state_at_matches_simulation() {
+        let next = |&n: &u32| (n * n + 1) % 255;
+        let cycle = find_cycle_from(3, next);
+        let mut state = 3;
+        for step in 0..1000 {
+            assert_eq!(*cycle.state_at(step), state);
+            state = next(&state);
+        }
+    }
+
+    #[test]
+    fn test_finite_without_repeat() {
+        assert!(find_cycle(0..10).is_none());
+        let cycle = find_cycle([1, 1]).unwrap();
+        assert_eq!((cycle.prefix_len, cycle.period), (0, 1));
+    }
+}
@@ -0,0 +1,236 @@
+use std::{
+    collections::HashMap,
+    ops::{Index, IndexMut},
+};
+
+use color_eyre::{eyre::anyhow, Result};
+
+/// Dense id of a name in an [`Interner`], usable as an index into arrays
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Id(u32);
+
+impl Id {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Assigns ids to names in the order they are first seen
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Interner {
+    ids: HashMap<String, Id>,
+    names: Vec<String>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn intern(&mut self, name: &str) -> Id {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+        let id = Id(u32::try_from(self.names.len()).expect("fewer than 2³² names"));
+        self.ids.insert(name.to_string(), id);
+        self.names.push(name.to_string());
+        id
+    }
+
+    pub fn get(&self, name: &str) -> Option<Id> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: Id) -> &str {
+        &self.names[id.index()]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Id, &str)> + '_ {
+        self.names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (Id(i as u32), name.as_str()))
+    }
+}
+
+/// Collects named nodes that may refer to each other before they are defined
+#[derive(Debug, Clone)]
+pub struct GraphBuilder<T> {
+    names: Interner,
+    nodes: Vec<Option<T>>,
+}
+
+impl<T> Default for GraphBuilder<T> {
+    fn default() -> Self {
+        Self {
+            names: Interner::default(),
+            nodes: vec![],
+        }
+    }
+}
+
+impl<T> GraphBuilder<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Id of `name`, whether it's defined yet or not
+    pub fn id(&mut self, name: &str) -> Id {
+        let id = self.names.intern(name);
+        if id.index() == self.nodes.len() {
+            self.nodes.push(None);
+        }
+        id
+    }
+
+    pub fn define(&mut self, name: &str, node: T) -> Result<Id> {
+        let id = self.id(name);
+        match &mut self.nodes[id.index()] {
+            Some(_) => Err(anyhow!("{name} is defined twice")),
+            slot => {
+                *slot = Some(node);
+                Ok(id)
+            }
+        }
+    }
+
+    /// Fails if a name was referred to but never defined
+    pub fn build(self) -> Result<Graph<T>> {
+        if let Some(id) = self.nodes.iter().position(Option::is_none) {
+            return Err(anyhow!("{} is never defined", self.names.names[id]));
+        }
+        Ok(Graph {
+            nodes: self.nodes.into_iter().flatten().collect(),
+            names: self.names,
+        })
+    }
+
+    /// Fills the names that were referred to but never defined with `undefined`
+    pub fn build_with(self, mut undefined: impl FnMut(&str) -> T) -> Graph<T> {
+        let nodes = self
+            .nodes
+            .into_iter()
+            .zip(&self.names.names)
+            .map(|(node, name)| node.unwrap_or_else(|| undefined(name)))
+            .collect();
+        Graph {
+            names: self.names,
+            nodes,
+        }
+    }
+}
+
+/// Nodes stored by the dense id of their name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Graph<T> {
+    names: Interner,
+    nodes: Vec<T>,
+}
+
+impl<T> Graph<T> {
+    pub fn id(&self, name: &str) -> Option<Id> {
+        self.names.get(name)
+    }
+
+    pub fn name(&self, id: Id) -> &str {
+        self.names.name(id)
+    }
+
+    pub fn names(&self) -> &Interner {
+        &self.names
+    }
+
+    pub fn get(&self, name: &str) -> Option<&T> {
+        self.id(name).map(|id| &self[id])
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = Id> {
+        (0..self.nodes.len() as u32).map(Id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Id, &T)> + '_ {
+        self.ids().zip(&self.nodes)
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Id, &mut T)> + '_ {
+        self.ids().zip(&mut self.nodes)
+    }
+
+    /// A graph with the same ids and the nodes mapped by `f`
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Graph<U> {
+        Graph {
+            names: self.names,
+            nodes: self.nodes.into_iter().map(f).collect(),
+        }
+    }
+}
+
+impl<T> Index<Id> for Graph<T> {
+    type Output = T;
+
+    fn index(&self, id: Id) -> &T {
+        &self.nodes[id.index()]
+    }
+}
+
+impl<T> IndexMut<Id> for Graph<T> {
+    fn index_mut(&mut self, id: Id) -> &mut T {
+        &mut self.nodes[id.index()]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{GraphBuilder, Interner};
+
+    #[test]
+    fn test_interner() {
+        let mut interner = Interner::new();
+        let aaa = interner.intern("AAA");
+        let bbb = interner.intern("BBB");
+        assert_eq!(interner.intern("AAA"), aaa);
+        assert_eq!((aaa.index(), bbb.index()), (0, 1));
+        assert_eq!(interner.name(bbb), "BBB");
+        assert_eq!(interner.get("CCC"), None);
+        assert_eq!(
+            interner.iter().collect::<Vec<_>>(),
+            [(aaa, "AAA"), (bbb, "BBB")]
+        );
+    }
+
+    #[test]
+    fn test_graph_builder() {
+        let mut builder = GraphBuilder::new();
+        let b = builder.id("b");
+        let a = builder.define("a", vec![b]).unwrap();
+        assert!(builder.clone().build().is_err());
+        assert!(builder.define("a", vec![]).is_err());
+        builder.define("b", vec![a, b]).unwrap();
+        let graph = builder.clone().build().unwrap();
+        assert_eq!(graph[b], [a, b]);
+        assert_eq!(graph.get("a"), Some(&vec![b]));
+        assert_eq!(graph.name(a), "a");
+        assert_eq!(graph.len(), 2);
+
+        builder.id("c");
+        let graph = builder.build_with(|_| vec![]).map(|edges| edges.len());
+        assert_eq!(graph.iter().map(|(_, n)| *n).collect::<Vec<_>>(), [2, 1, 0]);
+        assert_eq!(graph.id("c").map(|id| graph[id]), Some(0));
+    }
+}
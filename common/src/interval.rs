@@ -0,0 +1,373 @@
+use std::ops::{Add, Range, Sub};
+
+/// Integer types that can be used as interval bounds.
+pub trait Bound: Copy + Ord + Add<Output = Self> + Sub<Output = Self> {
+    const ZERO: Self;
+    const ONE: Self;
+
+    /// Widens the value so products of lengths don't overflow.
+    fn widen(self) -> i128;
+}
+
+macro_rules! impl_bound {
+    ($($t:ty),*) => {
+        $(
+            impl Bound for $t {
+                const ZERO: Self = 0;
+                const ONE: Self = 1;
+
+                fn widen(self) -> i128 {
+                    self as i128
+                }
+            }
+        )*
+    };
+}
+
+impl_bound!(i16, i32, i64, isize, u16, u32, u64, usize);
+
+/// A set of values stored as sorted, disjoint and non-adjacent half-open ranges.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IntervalSet<T> {
+    ranges: Vec<Range<T>>,
+}
+
+impl<T: Bound> Default for IntervalSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Bound> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self { ranges: vec![] }
+    }
+
+    pub fn ranges(&self) -> &[Range<T>] {
+        &self.ranges
+    }
+
+    pub fn into_ranges(self) -> Vec<Range<T>> {
+        self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn min(&self) -> Option<T> {
+        self.ranges.first().map(|r| r.start)
+    }
+
+    pub fn max(&self) -> Option<T> {
+        self.ranges.last().map(|r| r.end - T::ONE)
+    }
+
+    /// Total number of values in the set, widened like [`HyperRect::volume`]
+    /// so it doesn't overflow for sets spanning the whole type.
+    pub fn len(&self) -> u128 {
+        self.ranges
+            .iter()
+            .map(|r| (r.end.widen() - r.start.widen()) as u128)
+            .sum()
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        let idx = self.ranges.partition_point(|r| r.end <= *value);
+        self.ranges.get(idx).is_some_and(|r| r.contains(value))
+    }
+
+    pub fn insert(&mut self, range: Range<T>) {
+        if range.is_empty() {
+            return;
+        }
+        // First range that touches or overlaps the new one, and the first one after it
+        let lo = self.ranges.partition_point(|r| r.end < range.start);
+        let hi = self.ranges.partition_point(|r| r.start <= range.end);
+        let merged = if lo < hi {
+            self.ranges[lo].start.min(range.start)..self.ranges[hi - 1].end.max(range.end)
+        } else {
+            range
+        };
+        self.ranges.splice(lo..hi, [merged]);
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        let mut result = self.clone();
+        for range in &other.ranges {
+            result.insert(range.clone());
+        }
+        result
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = vec![];
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (a, b) = (&self.ranges[i], &other.ranges[j]);
+            let start = a.start.max(b.start);
+            let end = a.end.min(b.end);
+            if start < end {
+                ranges.push(start..end);
+            }
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        Self { ranges }
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut ranges = vec![];
+        let mut j = 0;
+        for range in &self.ranges {
+            let mut cur = range.start;
+            while j < other.ranges.len() && other.ranges[j].end <= cur {
+                j += 1;
+            }
+            let mut k = j;
+            while k < other.ranges.len() && other.ranges[k].start < range.end {
+                let cut = &other.ranges[k];
+                if cur < cut.start {
+                    ranges.push(cur..cut.start);
+                }
+                cur = cur.max(cut.end);
+                k += 1;
+            }
+            if cur < range.end {
+                ranges.push(cur..range.end);
+            }
+        }
+        Self { ranges }
+    }
+
+    /// Splits the set into the values below `at` and the values at or above it.
+    pub fn split_at(&self, at: T) -> (Self, Self) {
+        let mut below = vec![];
+        let mut above = vec![];
+        for range in &self.ranges {
+            if range.end <= at {
+                below.push(range.clone());
+            } else if range.start >= at {
+                above.push(range.clone());
+            } else {
+                below.push(range.start..at);
+                above.push(at..range.end);
+            }
+        }
+        (Self { ranges: below }, Self { ranges: above })
+    }
+
+    /// Moves every value of the set up by `offset`.
+    pub fn shift(&self, offset: T) -> Self {
+        Self {
+            ranges: self
+                .ranges
+                .iter()
+                .map(|r| r.start + offset..r.end + offset)
+                .collect(),
+        }
+    }
+
+    /// Moves every value of the set down by `offset`, which `shift` can't do
+    /// for unsigned types.
+    pub fn shift_down(&self, offset: T) -> Self {
+        Self {
+            ranges: self
+                .ranges
+                .iter()
+                .map(|r| r.start - offset..r.end - offset)
+                .collect(),
+        }
+    }
+}
+
+impl<T: Bound> From<Range<T>> for IntervalSet<T> {
+    fn from(range: Range<T>) -> Self {
+        let mut set = Self::new();
+        set.insert(range);
+        set
+    }
+}
+
+impl<T: Bound> FromIterator<Range<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> Self {
+        let mut ranges: Vec<_> = iter.into_iter().filter(|r| !r.is_empty()).collect();
+        ranges.sort_by_key(|r| r.start);
+        let mut result: Vec<Range<T>> = vec![];
+        for range in ranges {
+            match result.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => result.push(range),
+            }
+        }
+        Self { ranges: result }
+    }
+}
+
+impl<T: Bound> Extend<Range<T>> for IntervalSet<T> {
+    fn extend<I: IntoIterator<Item = Range<T>>>(&mut self, iter: I) {
+        for range in iter {
+            self.insert(range);
+        }
+    }
+}
+
+/// An axis aligned box with one half-open range per dimension.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HyperRect<T> {
+    axes: Vec<Range<T>>,
+}
+
+impl<T: Bound> HyperRect<T> {
+    pub fn new(axes: Vec<Range<T>>) -> Self {
+        Self { axes }
+    }
+
+    /// A box spanning `range` along each of the `dims` axes.
+    pub fn uniform(dims: usize, range: Range<T>) -> Self {
+        Self {
+            axes: vec![range; dims],
+        }
+    }
+
+    pub fn dims(&self) -> usize {
+        self.axes.len()
+    }
+
+    pub fn axes(&self) -> &[Range<T>] {
+        &self.axes
+    }
+
+    pub fn axis(&self, axis: usize) -> &Range<T> {
+        &self.axes[axis]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.axes.iter().any(|r| r.is_empty())
+    }
+
+    pub fn contains(&self, point: &[T]) -> bool {
+        point.len() == self.axes.len() && self.axes.iter().zip(point).all(|(r, p)| r.contains(p))
+    }
+
+    /// Number of integer points inside the box.
+    pub fn volume(&self) -> u128 {
+        if self.is_empty() {
+            return 0;
+        }
+        self.axes
+            .iter()
+            .map(|r| (r.end - r.start).widen() as u128)
+            .product()
+    }
+
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let axes: Vec<_> = self
+            .axes
+            .iter()
+            .zip(&other.axes)
+            .map(|(a, b)| a.start.max(b.start)..a.end.min(b.end))
+            .collect();
+        let rect = Self { axes };
+        (!rect.is_empty()).then_some(rect)
+    }
+
+    /// Splits the box along `axis` into the part below `at` and the part at or above it.
+    /// Empty halves are returned as `None`.
+    pub fn split_at(&self, axis: usize, at: T) -> (Option<Self>, Option<Self>) {
+        let range = &self.axes[axis];
+        let mid = at.max(range.start).min(range.end);
+        let mut below = self.clone();
+        below.axes[axis] = range.start..mid;
+        let mut above = self.clone();
+        above.axes[axis] = mid..range.end;
+        (
+            (!below.is_empty()).then_some(below),
+            (!above.is_empty()).then_some(above),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{HyperRect, IntervalSet};
+
+    #[test]
+    fn test_from_iter_merges() {
+        let set: IntervalSet<i64> = vec![50..52, 52..100, 1..50, 200..200].into_iter().collect();
+        assert_eq!(set, IntervalSet::from(1..100));
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut set = IntervalSet::from(10..20);
+        set.insert(30..40);
+        set.insert(0..5);
+        assert_eq!(set.ranges(), &[0..5, 10..20, 30..40]);
+        set.insert(5..10);
+        assert_eq!(set.ranges(), &[0..20, 30..40]);
+        set.insert(15..35);
+        assert_eq!(set, IntervalSet::from(0..40));
+        assert_eq!(set.len(), 40);
+
+        let full = IntervalSet::from(i64::MIN..i64::MAX);
+        assert_eq!(full.len(), u64::MAX as u128);
+        let full = IntervalSet::from(0..u64::MAX);
+        assert_eq!(full.len(), u64::MAX as u128);
+    }
+
+    #[test]
+    fn test_contains() {
+        let set: IntervalSet<i32> = vec![0..5, 10..20].into_iter().collect();
+        assert!(set.contains(&0));
+        assert!(!set.contains(&5));
+        assert!(set.contains(&19));
+        assert!(!set.contains(&20));
+        assert_eq!(set.min(), Some(0));
+        assert_eq!(set.max(), Some(19));
+    }
+
+    #[test]
+    fn test_union_intersection_difference() {
+        let a: IntervalSet<i64> = vec![0..10, 20..30].into_iter().collect();
+        let b: IntervalSet<i64> = vec![5..25, 28..40].into_iter().collect();
+        assert_eq!(a.union(&b), IntervalSet::from(0..40));
+        assert_eq!(a.intersection(&b).ranges(), &[5..10, 20..25, 28..30]);
+        assert_eq!(a.difference(&b).ranges(), &[0..5, 25..28]);
+        assert_eq!(b.difference(&a).ranges(), &[10..20, 30..40]);
+        assert!(a.difference(&a).is_empty());
+    }
+
+    #[test]
+    fn test_split_and_shift() {
+        let set: IntervalSet<i64> = vec![0..10, 20..30].into_iter().collect();
+        let (below, above) = set.split_at(25);
+        assert_eq!(below.ranges(), &[0..10, 20..25]);
+        assert_eq!(above, IntervalSet::from(25..30));
+        assert_eq!(set.shift(-5).ranges(), &[-5..5, 15..25]);
+        assert_eq!(set.shift_down(5), set.shift(-5));
+
+        let set: IntervalSet<u64> = vec![10..20, 30..40].into_iter().collect();
+        assert_eq!(set.shift_down(10).ranges(), &[0..10, 20..30]);
+        assert_eq!(set.shift(5).ranges(), &[15..25, 35..45]);
+    }
+
+    #[test]
+    fn test_hyper_rect() {
+        let rect = HyperRect::uniform(4, 1..4001_i64);
+        assert_eq!(rect.volume(), 4000_u128.pow(4));
+        let (below, above) = rect.split_at(1, 1000);
+        assert_eq!(below.as_ref().map(|r| r.axis(1).clone()), Some(1..1000));
+        assert_eq!(above.as_ref().map(|r| r.axis(1).clone()), Some(1000..4001));
+        let (below, above) = rect.split_at(0, 1);
+        assert!(below.is_none());
+        assert_eq!(above, Some(rect.clone()));
+        assert!(rect.contains(&[1, 2, 3, 4000]));
+        assert!(!rect.contains(&[1, 2, 3, 4001]));
+        let other = HyperRect::uniform(4, 4000..5000);
+        assert_eq!(rect.intersection(&other).map(|r| r.volume()), Some(1));
+    }
+}
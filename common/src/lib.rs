@@ -1,25 +1,65 @@
-use color_eyre::Result;
+pub mod answer;
+pub mod bucket_map;
+pub mod cycle;
+pub mod graph;
+pub mod interval;
+pub mod math;
+pub mod polygon;
+pub mod sequence;
+
+use color_eyre::{eyre::anyhow, Result};
 use std::{
     fs::File,
-    io::{BufRead, BufReader},
+    io::{self, BufRead, BufReader},
     path::{Path, PathBuf},
 };
 
 use clap::Parser;
 
-#[derive(Parser)]
+// Input selection shared by the binaries of every day. Days with extra
+// parameters flatten it into their own arguments. Not a doc comment, clap
+// would print it as the description of every binary.
+#[derive(Debug, Parser)]
 pub struct CommonCli {
-    #[arg(short, long)]
-    pub file: PathBuf,
+    /// Puzzle input, `-` reads it from stdin. Defaults to the personal input
+    #[arg(short, long, conflicts_with = "example")]
+    pub file: Option<PathBuf>,
+    /// Read the day's `inputs/example_N.txt` instead
+    #[arg(short, long, value_name = "N")]
+    pub example: Option<usize>,
 }
 
-pub fn get_file(filename: PathBuf) -> Result<Vec<String>> {
-    let file = File::open(filename)?;
-    let reader = BufReader::new(file);
+impl CommonCli {
+    /// Where the input comes from, with `inputs` looked up in the day's
+    /// `manifest_dir`
+    pub fn input_path(&self, manifest_dir: impl AsRef<Path>) -> PathBuf {
+        let inputs_dir = manifest_dir.as_ref().join("inputs");
+        match (&self.file, self.example) {
+            (Some(file), _) => file.clone(),
+            (None, Some(n)) => inputs_dir.join(format!("example_{n}.txt")),
+            (None, None) => inputs_dir.join("full.txt"),
+        }
+    }
+
+    pub fn read_input(&self, manifest_dir: impl AsRef<Path>) -> Result<Vec<String>> {
+        let path = self.input_path(manifest_dir);
+        if path == Path::new("-") {
+            return read_lines(io::stdin().lock());
+        }
+        get_file(path.clone()).map_err(|e| anyhow!("Can't read {}: {e}", path.display()))
+    }
+}
+
+pub fn read_lines(reader: impl BufRead) -> Result<Vec<String>> {
     let lines: std::result::Result<Vec<String>, _> = reader.lines().collect();
     Ok(lines?)
 }
 
+pub fn get_file(filename: PathBuf) -> Result<Vec<String>> {
+    let file = File::open(filename)?;
+    read_lines(BufReader::new(file))
+}
+
 pub fn copy_dir_all(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> std::io::Result<()> {
     if dst.as_ref().exists() {
         let dst_metadata = std::fs::metadata(&dst)?;
@@ -40,3 +80,32 @@ pub fn copy_dir_all(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> std::io::Re
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use clap::Parser;
+
+    use super::CommonCli;
+
+    #[test]
+    fn test_input_path() {
+        let day_dir = PathBuf::from("day_1");
+        let path = |args: &[&str]| {
+            CommonCli::try_parse_from([&["task_1"], args].concat())
+                .map(|cli| cli.input_path(&day_dir))
+        };
+        assert_eq!(path(&[]).unwrap(), day_dir.join("inputs/full.txt"));
+        assert_eq!(
+            path(&["--example", "2"]).unwrap(),
+            day_dir.join("inputs/example_2.txt")
+        );
+        assert_eq!(
+            path(&["-f", "/tmp/in.txt"]).unwrap(),
+            PathBuf::from("/tmp/in.txt")
+        );
+        assert_eq!(path(&["--file", "-"]).unwrap(), PathBuf::from("-"));
+        assert!(path(&["-f", "in.txt", "-e", "1"]).is_err());
+    }
+}
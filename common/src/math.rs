@@ -0,0 +1,72 @@
+pub fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+pub fn lcm(a: i128, b: i128) -> i128 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    (a / gcd(a, b) * b).abs()
+}
+
+/// Returns `(g, x, y)` with `a * x + b * y == g == gcd(a, b)`.
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (g, x, y) = extended_gcd(b, a % b);
+        (g, y, x - (a / b) * y)
+    }
+}
+
+/// Solves the system `x ≡ residue (mod modulus)` for every `(residue, modulus)`
+/// pair, moduli don't need to be coprime. Returns the smallest non-negative
+/// solution with the combined modulus, or `None` if the congruences contradict
+/// each other or a modulus isn't positive.
+pub fn crt(congruences: &[(i128, i128)]) -> Option<(i128, i128)> {
+    let mut result = (0, 1);
+    for &(residue, modulus) in congruences {
+        if modulus <= 0 {
+            return None;
+        }
+        let (r1, m1) = result;
+        let r2 = residue.rem_euclid(modulus);
+        let (g, p, _) = extended_gcd(m1, modulus);
+        if (r2 - r1) % g != 0 {
+            return None;
+        }
+        let combined = m1 / g * modulus;
+        let step = ((r2 - r1) / g * p).rem_euclid(modulus / g);
+        result = ((r1 + m1 * step).rem_euclid(combined), combined);
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{crt, gcd, lcm};
+
+    #[test]
+    fn test_gcd_lcm() {
+        assert_eq!(gcd(12, 18), 6);
+        assert_eq!(gcd(-12, 18), 6);
+        assert_eq!(lcm(4, 6), 12);
+        assert_eq!(lcm(3917, 4003), 3917 * 4003);
+    }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        // Moduli sharing a factor
+        assert_eq!(crt(&[(2, 4), (4, 6)]), Some((10, 12)));
+        assert_eq!(crt(&[(1, 4), (2, 6)]), None);
+        assert_eq!(crt(&[]), Some((0, 1)));
+        // Moduli have to be positive
+        assert_eq!(crt(&[(2, 3), (1, 0)]), None);
+        assert_eq!(crt(&[(2, 3), (1, -5)]), None);
+    }
+}
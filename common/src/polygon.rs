@@ -0,0 +1,196 @@
+use color_eyre::{eyre::anyhow, Result};
+
+use crate::{answer::Checked, math::gcd};
+
+pub type Point = (i64, i64);
+
+/// A simple polygon on the integer lattice. The last vertex connects back to
+/// the first one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polygon {
+    vertices: Vec<Point>,
+}
+
+impl Polygon {
+    /// A trailing vertex equal to the first one is dropped, so both closed and
+    /// unclosed paths describe the same polygon.
+    pub fn new(mut vertices: Vec<Point>) -> Self {
+        if vertices.len() > 1 && vertices.first() == vertices.last() {
+            vertices.pop();
+        }
+        Self { vertices }
+    }
+
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    /// Every edge including the one closing the polygon.
+    pub fn edges(&self) -> impl Iterator<Item = (Point, Point)> + '_ {
+        self.vertices
+            .iter()
+            .copied()
+            .zip(self.vertices.iter().copied().cycle().skip(1))
+    }
+
+    /// Twice the signed area by the shoelace formula, positive when the
+    /// vertices go counter-clockwise with y pointing up. Fails on overflow.
+    pub fn double_signed_area(&self) -> Result<i64> {
+        self.edges().try_fold(0i64, |area, ((x1, y1), (x2, y2))| {
+            area.try_add(x1.try_mul(y2)?.try_sub(x2.try_mul(y1)?)?)
+        })
+    }
+
+    /// Twice the area, which is always an integer for lattice polygons.
+    pub fn double_area(&self) -> Result<i64> {
+        let area = self.double_signed_area()?;
+        area.checked_abs()
+            .ok_or_else(|| anyhow!("Overflow computing |{area}|"))
+    }
+
+    /// Number of lattice points on the edges.
+    pub fn boundary_points(&self) -> Result<i64> {
+        if self.vertices.len() < 2 {
+            return Ok(self.vertices.len() as i64);
+        }
+        self.edges().try_fold(0i64, |points, ((x1, y1), (x2, y2))| {
+            let edge_points = gcd(x2 as i128 - x1 as i128, y2 as i128 - y1 as i128);
+            points.try_add(i64::try_from(edge_points)?)
+        })
+    }
+
+    /// Number of lattice points strictly inside, by Pick's theorem
+    /// `A = I + B / 2 - 1`.
+    pub fn interior_points(&self) -> Result<i64> {
+        let double_area = self.double_area()?;
+        if double_area == 0 {
+            return Ok(0);
+        }
+        Ok(double_area.try_sub(self.boundary_points()?)?.try_add(2)? / 2)
+    }
+
+    /// Number of lattice points inside or on the boundary.
+    pub fn lattice_points(&self) -> Result<i64> {
+        self.interior_points()?.try_add(self.boundary_points()?)
+    }
+
+    pub fn on_boundary(&self, (px, py): Point) -> bool {
+        self.edges().any(|((x1, y1), (x2, y2))| {
+            let cross = cross((x1, y1), (x2, y2), (px, py));
+            cross == 0
+                && (x1.min(x2)..=x1.max(x2)).contains(&px)
+                && (y1.min(y2)..=y1.max(y2)).contains(&py)
+        })
+    }
+
+    /// Whether `point` lies strictly inside, by casting a ray towards +x and
+    /// counting the edges it crosses.
+    pub fn contains(&self, point: Point) -> bool {
+        if self.on_boundary(point) {
+            return false;
+        }
+        let py = point.1;
+        self.edges()
+            .filter(|&((x1, y1), (x2, y2))| {
+                if (y1 > py) == (y2 > py) {
+                    return false;
+                }
+                // The point is left of the crossing iff this has the sign of dy
+                (cross((x1, y1), (x2, y2), point) > 0) == (y2 > y1)
+            })
+            .count()
+            % 2
+            == 1
+    }
+}
+
+/// Cross product of `b - a` and `p - a`, widened so it can't overflow.
+fn cross((ax, ay): Point, (bx, by): Point, (px, py): Point) -> i128 {
+    let [ax, ay, bx, by, px, py] = [ax, ay, bx, by, px, py].map(i128::from);
+    (bx - ax) * (py - ay) - (px - ax) * (by - ay)
+}
+
+#[cfg(test)]
+mod test {
+    use color_eyre::Result;
+
+    use super::Polygon;
+
+    #[test]
+    fn test_square() -> Result<()> {
+        let square = Polygon::new(vec![(0, 0), (4, 0), (4, 4), (0, 4)]);
+        assert_eq!(square.double_signed_area()?, 32);
+        assert_eq!(square.boundary_points()?, 16);
+        assert_eq!(square.interior_points()?, 9);
+        assert_eq!(square.lattice_points()?, 25);
+
+        // Clockwise and explicitly closed
+        let square = Polygon::new(vec![(0, 0), (0, 4), (4, 4), (4, 0), (0, 0)]);
+        assert_eq!(square.vertices().len(), 4);
+        assert_eq!(square.double_signed_area()?, -32);
+        assert_eq!(square.lattice_points()?, 25);
+        Ok(())
+    }
+
+    #[test]
+    fn test_triangle() -> Result<()> {
+        let triangle = Polygon::new(vec![(0, 0), (3, 0), (0, 3)]);
+        assert_eq!(triangle.double_area()?, 9);
+        assert_eq!(triangle.boundary_points()?, 9);
+        assert_eq!(triangle.interior_points()?, 1);
+        assert!(triangle.contains((1, 1)));
+        assert!(!triangle.contains((2, 1)));
+        assert!(triangle.on_boundary((2, 1)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_contains_concave() -> Result<()> {
+        // U shape open towards +y
+        let u = Polygon::new(vec![
+            (0, 0),
+            (6, 0),
+            (6, 6),
+            (4, 6),
+            (4, 2),
+            (2, 2),
+            (2, 6),
+            (0, 6),
+        ]);
+        assert!(u.contains((1, 1)));
+        assert!(u.contains((1, 5)));
+        assert!(u.contains((5, 5)));
+        assert!(!u.contains((3, 4)));
+        assert!(!u.contains((3, 2)));
+        assert!(!u.contains((7, 1)));
+        assert_eq!(
+            (0..=6)
+                .flat_map(|x| (0..=6).map(move |y| (x, y)))
+                .filter(|&p| u.contains(p))
+                .count() as i64,
+            u.interior_points()?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_degenerate() -> Result<()> {
+        assert_eq!(Polygon::new(vec![]).interior_points()?, 0);
+        assert_eq!(Polygon::new(vec![(1, 1)]).lattice_points()?, 1);
+        let line = Polygon::new(vec![(0, 0), (3, 0)]);
+        assert_eq!(line.interior_points()?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_overflow() -> Result<()> {
+        let max = i64::MAX;
+        let square = Polygon::new(vec![(0, 0), (max, 0), (max, max), (0, max)]);
+        assert!(square.double_area().is_err());
+        assert!(square.lattice_points().is_err());
+        let far = Polygon::new(vec![(-max, 0), (max, 0), (max, 1), (-max, 1)]);
+        assert!(far.boundary_points().is_err());
+        assert!(far.contains((0, 0)) || far.on_boundary((0, 0)));
+        Ok(())
+    }
+}
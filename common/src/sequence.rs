@@ -0,0 +1,212 @@
+use color_eyre::{eyre::anyhow, Result};
+
+use crate::{answer::Checked, math::gcd};
+
+/// A polynomial in Newton's forward difference form
+/// `p(x) = Σ Δʲ · C(x, j)`, where `Δʲ` is the `j`th forward difference of
+/// `p(0), p(1), ...` at `0`. Keeping it in this form makes every value at an
+/// integer an integer, with no fractions along the way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polynomial {
+    /// `Δ⁰ ... Δᵈ` with a non-zero last entry, empty for the zero polynomial
+    differences: Vec<i128>,
+}
+
+impl Polynomial {
+    /// Fits the polynomial through `(0, values[0]), (1, values[1]), ...` of
+    /// degree at most `max_degree`. The difference table has to reach a row of
+    /// zeros to prove there's no higher degree term, so `n` values can prove
+    /// a degree of at most `n - 2`.
+    pub fn fit(values: &[i64], max_degree: usize) -> Result<Self> {
+        let mut row: Vec<i128> = values.iter().map(|&v| v as i128).collect();
+        let mut differences = vec![];
+        while !row.is_empty() {
+            if row.iter().all(|&v| v == 0) {
+                return Ok(Self { differences });
+            }
+            if differences.len() > max_degree {
+                return Err(anyhow!(
+                    "{values:?} is not a polynomial of degree at most {max_degree}"
+                ));
+            }
+            differences.push(row[0]);
+            row = row
+                .windows(2)
+                .map(|w| w[1].try_sub(w[0]))
+                .collect::<Result<_>>()?;
+        }
+        Err(anyhow!(
+            "{} values can't prove {values:?} is a polynomial of degree at most {max_degree}",
+            values.len()
+        ))
+    }
+
+    /// The polynomial of lowest degree through all `n` values, of degree at
+    /// most `n - 1`. Unlike [`Polynomial::fit`], running out of values counts
+    /// as reaching a row of zeros, so this can't check the degree.
+    pub fn interpolate(values: &[i64]) -> Result<Self> {
+        let mut row: Vec<i128> = values.iter().map(|&v| v as i128).collect();
+        let mut differences = vec![];
+        while row.iter().any(|&v| v != 0) {
+            differences.push(row[0]);
+            row = row
+                .windows(2)
+                .map(|w| w[1].try_sub(w[0]))
+                .collect::<Result<_>>()?;
+        }
+        Ok(Self { differences })
+    }
+
+    /// Degree, taking the zero polynomial as a constant
+    pub fn degree(&self) -> usize {
+        self.differences.len().saturating_sub(1)
+    }
+
+    /// `Δ⁰ ... Δᵈ`, the coefficients of `C(x, j)`
+    pub fn forward_differences(&self) -> &[i128] {
+        &self.differences
+    }
+
+    /// Value at any integer `x`, negative ones included
+    pub fn eval(&self, x: i64) -> Result<i128> {
+        let x = x as i128;
+        let mut binomial: i128 = 1;
+        let mut value: i128 = 0;
+        for (j, &difference) in self.differences.iter().enumerate() {
+            value = value.try_add(binomial.try_mul(difference)?)?;
+            // C(x, j + 1) = C(x, j) · (x - j) / (j + 1), which divides exactly
+            let j = j as i128;
+            binomial = binomial.try_mul(x - j)? / (j + 1);
+        }
+        Ok(value)
+    }
+
+    /// Coefficients `c₀ ... cᵈ` and a denominator `q` in lowest terms with
+    /// `p(x) = (c₀ + c₁x + ... + cᵈxᵈ) / q`
+    pub fn coefficients(&self) -> Result<(Vec<i128>, i128)> {
+        let degree = self.degree();
+        let factorial = |n: usize| (1..=n as i128).try_fold(1, |acc: i128, i| acc.try_mul(i));
+        let denominator = factorial(degree)?;
+        let mut coefficients = vec![0; degree + 1];
+        // Coefficients of the falling factorial x (x - 1) ... (x - j + 1)
+        let mut falling = vec![1];
+        for (j, &difference) in self.differences.iter().enumerate() {
+            let scale = difference.try_mul(denominator / factorial(j)?)?;
+            for (c, &f) in coefficients.iter_mut().zip(&falling) {
+                *c = c.try_add(scale.try_mul(f)?)?;
+            }
+            let mut next = vec![0; falling.len() + 1];
+            for (k, &f) in falling.iter().enumerate() {
+                next[k + 1] = next[k + 1].try_add(f)?;
+                next[k] = next[k].try_sub((j as i128).try_mul(f)?)?;
+            }
+            falling = next;
+        }
+        let divisor = coefficients.iter().fold(denominator, |acc, &c| gcd(acc, c));
+        Ok((
+            coefficients.iter().map(|c| c / divisor).collect(),
+            denominator / divisor,
+        ))
+    }
+}
+
+/// A finite sequence of known values and the polynomial continuing it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sequence {
+    len: usize,
+    polynomial: Polynomial,
+}
+
+impl Sequence {
+    /// See [`Polynomial::fit`]
+    pub fn fit(values: &[i64], max_degree: usize) -> Result<Self> {
+        Ok(Self {
+            len: values.len(),
+            polynomial: Polynomial::fit(values, max_degree)?,
+        })
+    }
+
+    /// See [`Polynomial::interpolate`]
+    pub fn interpolate(values: &[i64]) -> Result<Self> {
+        Ok(Self {
+            len: values.len(),
+            polynomial: Polynomial::interpolate(values)?,
+        })
+    }
+
+    pub fn polynomial(&self) -> &Polynomial {
+        &self.polynomial
+    }
+
+    /// Value `k` steps after the last known one
+    pub fn forward(&self, k: i64) -> Result<i128> {
+        self.polynomial.eval((self.len as i64 - 1).try_add(k)?)
+    }
+
+    /// Value `k` steps before the first known one
+    pub fn backward(&self, k: i64) -> Result<i128> {
+        self.polynomial.eval(0.try_sub(k)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Polynomial, Sequence};
+
+    #[test]
+    fn test_extrapolate() {
+        // n² + 1
+        let sequence = Sequence::fit(&[1, 2, 5, 10, 17], 3).unwrap();
+        assert_eq!(sequence.polynomial().degree(), 2);
+        assert_eq!(sequence.polynomial().forward_differences(), [1, 1, 2]);
+        assert_eq!(sequence.forward(1).unwrap(), 26);
+        assert_eq!(sequence.forward(5).unwrap(), 82);
+        assert_eq!(sequence.backward(1).unwrap(), 2);
+        assert_eq!(sequence.backward(3).unwrap(), 10);
+        assert_eq!(sequence.forward(0).unwrap(), 17);
+        assert_eq!(sequence.backward(0).unwrap(), 1);
+
+        let constant = Sequence::fit(&[0, 0], 0).unwrap();
+        assert_eq!(constant.forward(100).unwrap(), 0);
+        assert_eq!(constant.polynomial().degree(), 0);
+    }
+
+    #[test]
+    fn test_not_polynomial() {
+        let powers = [1, 2, 4, 8, 16, 32, 64];
+        assert!(Polynomial::fit(&powers, 3).is_err());
+        // Not enough values to reach a row of zeros
+        assert!(Polynomial::fit(&powers, 10).is_err());
+        assert!(Polynomial::fit(&[1, 2, 4], 1).is_err());
+        assert!(Polynomial::fit(&[], 5).is_err());
+        assert!(Polynomial::fit(&[3, 5, 7], 1).is_ok());
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let line = Sequence::interpolate(&[3, 5]).unwrap();
+        assert_eq!(line.polynomial().degree(), 1);
+        assert_eq!(line.forward(1).unwrap(), 7);
+        assert_eq!(line.backward(1).unwrap(), 1);
+        let powers = Polynomial::interpolate(&[1, 2, 4, 8]).unwrap();
+        assert_eq!(powers.forward_differences(), [1, 1, 1, 1]);
+        assert_eq!(powers.eval(4).unwrap(), 15);
+        assert_eq!(Polynomial::interpolate(&[4]).unwrap().eval(3).unwrap(), 4);
+        assert_eq!(Polynomial::interpolate(&[]).unwrap().degree(), 0);
+        assert_eq!(
+            Polynomial::interpolate(&[1, 2, 5, 10, 17]).unwrap(),
+            Polynomial::fit(&[1, 2, 5, 10, 17], 3).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_coefficients() {
+        // Triangular numbers n (n + 1) / 2
+        let triangular = Polynomial::fit(&[0, 1, 3, 6, 10], 2).unwrap();
+        assert_eq!(triangular.coefficients().unwrap(), (vec![0, 1, 1], 2));
+        // 2n³ - n + 7
+        let cubic = Polynomial::fit(&[7, 8, 21, 58, 131, 252], 3).unwrap();
+        assert_eq!(cubic.coefficients().unwrap(), (vec![7, -1, 0, 2], 1));
+        assert_eq!(cubic.eval(-2).unwrap(), -7);
+    }
+}
@@ -1,55 +1,42 @@
 use color_eyre::{eyre::anyhow, Result};
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use common::answer::{Answer, CheckedIterator};
 
-pub fn solve_task_one(input: Vec<String>) -> Result<i32> {
-    let lines = input;
-
-    let lines = lines
-        .into_par_iter()
-        .map(|line| {
-            line.chars()
-                .filter_map(|c| c.to_digit(10))
-                .collect::<Vec<_>>()
-        })
-        .collect::<Vec<_>>();
-
-    let sol = lines
-        .into_par_iter()
-        .map(|line| line.first().unwrap() * 10 + line.last().unwrap())
-        .sum::<u32>();
-    i32::try_from(sol).map_err(|_| anyhow!("Number doesn't fit!"))
+/// The first and the last digit of `line` as a two digit number
+fn calibration_value(line: &str) -> Result<u64> {
+    let mut digits = line.chars().filter_map(|c| c.to_digit(10));
+    let first = digits.next().ok_or(anyhow!("No digit in line: {line}"))?;
+    let last = digits.next_back().unwrap_or(first);
+    Ok((first * 10 + last) as u64)
 }
 
-pub fn solve_task_two(input: Vec<String>) -> Result<i32> {
-    let lines = input
-        .into_par_iter()
-        .map(|line| {
-            line.replace("one", "o1e")
-                .replace("two", "t2o")
-                .replace("three", "t3e")
-                .replace("four", "f4r")
-                .replace("five", "f5e")
-                .replace("six", "s6x")
-                .replace("seven", "s7n")
-                .replace("eight", "e8t")
-                .replace("nine", "n9e")
-        })
-        .collect::<Vec<_>>();
+pub fn solve_task_one(input: Vec<String>) -> Result<Answer> {
+    let values = input
+        .iter()
+        .map(|line| calibration_value(line))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(values.into_iter().try_sum()?.into())
+}
 
-    let lines = lines
-        .into_par_iter()
+pub fn solve_task_two(input: Vec<String>) -> Result<Answer> {
+    let values = input
+        .iter()
         .map(|line| {
-            line.chars()
-                .filter_map(|c| c.to_digit(10))
-                .collect::<Vec<_>>()
+            // Keep the first and last letter so overlapping words still match
+            calibration_value(
+                &line
+                    .replace("one", "o1e")
+                    .replace("two", "t2o")
+                    .replace("three", "t3e")
+                    .replace("four", "f4r")
+                    .replace("five", "f5e")
+                    .replace("six", "s6x")
+                    .replace("seven", "s7n")
+                    .replace("eight", "e8t")
+                    .replace("nine", "n9e"),
+            )
         })
-        .collect::<Vec<_>>();
-
-    let sol = lines
-        .into_par_iter()
-        .map(|line| line.first().unwrap() * 10 + line.last().unwrap())
-        .sum::<u32>();
-    i32::try_from(sol).map_err(|_| anyhow!("Number doesn't fit!"))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(values.into_iter().try_sum()?.into())
 }
 
 #[cfg(test)]
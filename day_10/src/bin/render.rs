@@ -0,0 +1,22 @@
+use clap::Parser;
+use color_eyre::Result;
+
+use common::CommonCli;
+
+/// Prints a day 10 pipe map with the loop and the enclosed tiles highlighted
+#[derive(Parser)]
+struct Args {
+    #[command(flatten)]
+    input: CommonCli,
+    /// Print plain characters without terminal colors
+    #[arg(long)]
+    no_color: bool,
+}
+
+fn main() -> Result<()> {
+    color_eyre::install()?;
+    let args = Args::parse();
+    let file = args.input.read_input(env!("CARGO_MANIFEST_DIR"))?;
+    print!("{}", solution::render(file, !args.no_color)?);
+    Ok(())
+}
@@ -1,15 +1,12 @@
-use std::{
-    collections::{HashSet, VecDeque},
-    fmt::Debug,
-    time::Instant,
-};
+use std::time::Instant;
 
 use color_eyre::{
     eyre::{anyhow, Report},
     Result,
 };
+use common::{answer::Answer, polygon::Polygon};
 
-#[derive(Debug, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 enum Pipe {
     NorthSouth,
     WestEast,
@@ -36,14 +33,25 @@ impl TryFrom<&char> for Pipe {
 }
 
 impl Pipe {
-    fn get_directions(&self) -> Vec<Direction> {
+    fn get_directions(&self) -> [Direction; 2] {
+        match self {
+            Pipe::NorthSouth => [Direction::North, Direction::South],
+            Pipe::WestEast => [Direction::West, Direction::East],
+            Pipe::NorthWest => [Direction::North, Direction::West],
+            Pipe::NorthEast => [Direction::North, Direction::East],
+            Pipe::SouthWest => [Direction::South, Direction::West],
+            Pipe::SouthEast => [Direction::South, Direction::East],
+        }
+    }
+
+    fn box_char(&self) -> char {
         match self {
-            Pipe::NorthSouth => vec![Direction::North, Direction::South],
-            Pipe::WestEast => vec![Direction::West, Direction::East],
-            Pipe::NorthWest => vec![Direction::North, Direction::West],
-            Pipe::NorthEast => vec![Direction::North, Direction::East],
-            Pipe::SouthWest => vec![Direction::South, Direction::West],
-            Pipe::SouthEast => vec![Direction::South, Direction::East],
+            Pipe::NorthSouth => '│',
+            Pipe::WestEast => '─',
+            Pipe::NorthWest => '┘',
+            Pipe::NorthEast => '└',
+            Pipe::SouthWest => '┐',
+            Pipe::SouthEast => '┌',
         }
     }
 
@@ -59,7 +67,7 @@ impl Pipe {
     }
 }
 
-#[derive(Debug, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd)]
 enum Direction {
     North,
     South,
@@ -68,6 +76,15 @@ enum Direction {
 }
 
 impl Direction {
+    fn directions() -> [Direction; 4] {
+        [
+            Direction::North,
+            Direction::South,
+            Direction::West,
+            Direction::East,
+        ]
+    }
+
     fn get_opposite(&self) -> Direction {
         match self {
             Direction::North => Direction::South,
@@ -76,6 +93,17 @@ impl Direction {
             Direction::East => Direction::West,
         }
     }
+
+    /// The position one step from `(x, y)`, `None` when that leaves the top
+    /// or left edge.
+    fn step(&self, (x, y): (usize, usize)) -> Option<(usize, usize)> {
+        match self {
+            Direction::North => Some((x, y.checked_sub(1)?)),
+            Direction::South => Some((x, y + 1)),
+            Direction::West => Some((x.checked_sub(1)?, y)),
+            Direction::East => Some((x + 1, y)),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -99,23 +127,10 @@ impl TryFrom<&char> for Tile {
 }
 
 impl Tile {
-    fn get_directions(&self) -> Vec<Direction> {
-        match self {
-            Tile::Ground => vec![],
-            Tile::Pipe(pipe) => pipe.get_directions(),
-            Tile::Start => vec![
-                Direction::North,
-                Direction::South,
-                Direction::West,
-                Direction::East,
-            ],
-        }
-    }
-
     fn can_connect(&self, other: &Direction) -> bool {
         match self {
             Tile::Ground => false,
-            Tile::Pipe(pipe) => pipe.get_directions().contains(&other),
+            Tile::Pipe(pipe) => pipe.get_directions().contains(other),
             Tile::Start => true,
         }
     }
@@ -146,222 +161,185 @@ impl Map {
         self.tiles.get(y).and_then(|row| row.get(x))
     }
 
-    fn get_adjacent_tile(&self, (x, y): (usize, usize), direction: &Direction) -> Option<&Tile> {
-        match direction {
-            Direction::North => {
-                if y == 0 {
-                    None
-                } else {
-                    self.get_tile(x, y - 1)
-                }
-            }
-            Direction::South => self.get_tile(x, y + 1),
-            Direction::West => {
-                if x == 0 {
-                    None
-                } else {
-                    self.get_tile(x - 1, y)
-                }
-            }
-            Direction::East => self.get_tile(x + 1, y),
-        }
+    fn get_adjacent_tile(&self, pos: (usize, usize), direction: &Direction) -> Option<&Tile> {
+        let (x, y) = direction.step(pos)?;
+        self.get_tile(x, y)
     }
 
-    fn get_start(&self) -> (usize, usize) {
+    fn get_start(&self) -> Result<(usize, usize)> {
         for (y, row) in self.tiles.iter().enumerate() {
             for (x, tile) in row.iter().enumerate() {
                 if let Tile::Start = tile {
-                    return (x, y);
+                    return Ok((x, y));
                 }
             }
         }
-        panic!("No start found");
+        Err(anyhow!("No start found"))
     }
 
-    fn get_start_pipe_type(&self) -> Pipe {
-        let start_loc = self.get_start();
-        let (x, y) = start_loc;
-        let tile = self.get_tile(x, y).unwrap();
-        match tile {
-            Tile::Start => {
-                let mut maybe_start: HashSet<Pipe> = HashSet::from_iter(Pipe::pipes().into_iter());
-                let north_tile = self
-                    .get_adjacent_tile(start_loc, &Direction::North)
-                    .unwrap();
-                if north_tile.can_connect(&Direction::South) {
-                    maybe_start.remove(&Pipe::SouthEast);
-                    maybe_start.remove(&Pipe::SouthWest);
-                    maybe_start.remove(&Pipe::WestEast);
-                }
-                let east_tile = self.get_adjacent_tile(start_loc, &Direction::East).unwrap();
-                if east_tile.can_connect(&Direction::West) {
-                    maybe_start.remove(&Pipe::NorthWest);
-                    maybe_start.remove(&Pipe::SouthWest);
-                    maybe_start.remove(&Pipe::NorthSouth);
-                }
-                let south_tile = self
-                    .get_adjacent_tile(start_loc, &Direction::South)
-                    .unwrap();
-                if south_tile.can_connect(&Direction::North) {
-                    maybe_start.remove(&Pipe::NorthEast);
-                    maybe_start.remove(&Pipe::NorthWest);
-                    maybe_start.remove(&Pipe::WestEast);
-                }
-                if let Some(west_tile) = self.get_adjacent_tile(start_loc, &Direction::West) {
-                    if west_tile.can_connect(&Direction::East) {
-                        maybe_start.remove(&Pipe::NorthEast);
-                        maybe_start.remove(&Pipe::SouthEast);
-                        maybe_start.remove(&Pipe::NorthSouth);
-                    }
-                };
-
-                if maybe_start.len() != 1 {
-                    panic!("Invalid start pipe");
-                }
-                maybe_start.into_iter().next().unwrap()
-            }
-            _ => panic!("Invalid start pipe"),
+    /// Infers the pipe under the start tile from the neighbours that connect
+    /// back to it. Neighbours beyond the edge of the map never connect.
+    fn get_start_pipe_type(&self) -> Result<Pipe> {
+        let start = self.get_start()?;
+        let connected = Direction::directions()
+            .into_iter()
+            .filter(|direction| {
+                matches!(
+                    self.get_adjacent_tile(start, direction),
+                    Some(tile @ Tile::Pipe(_)) if tile.can_connect(&direction.get_opposite())
+                )
+            })
+            .collect::<Vec<_>>();
+        if connected.len() != 2 {
+            return Err(anyhow!(
+                "Start connects to {} pipes instead of 2",
+                connected.len()
+            ));
         }
+        Pipe::pipes()
+            .into_iter()
+            .find(|pipe| connected.iter().all(|d| pipe.get_directions().contains(d)))
+            .ok_or(anyhow!("No pipe fits the start tile"))
     }
+}
 
-    fn update_tile(&mut self, (x, y): (usize, usize), tile: Tile) {
-        self.tiles[y][x] = tile;
+/// Walks the loop through the start tile and returns its tiles in order,
+/// starting with the start tile.
+fn get_loop(map: &Map) -> Result<Vec<(usize, usize)>> {
+    let start = map.get_start()?;
+    let mut direction = map.get_start_pipe_type()?.get_directions()[0];
+    let mut pos = start;
+    let mut path = vec![start];
+    loop {
+        pos = direction
+            .step(pos)
+            .ok_or(anyhow!("Loop leaves the map at {pos:?}"))?;
+        if pos == start {
+            return Ok(path);
+        }
+        let came_from = direction.get_opposite();
+        direction = match map.get_tile(pos.0, pos.1) {
+            Some(Tile::Pipe(pipe)) if pipe.get_directions().contains(&came_from) => pipe
+                .get_directions()
+                .into_iter()
+                .find(|d| *d != came_from)
+                .ok_or(anyhow!("Pipe at {pos:?} turns back"))?,
+            _ => return Err(anyhow!("Loop is broken at {pos:?}")),
+        };
+        path.push(pos);
     }
 }
 
-fn get_loop(map: &Map, start_pos: (usize, usize)) -> Vec<(usize, usize)> {
-    let mut queue = VecDeque::from(vec![start_pos]);
-    let mut visited = HashSet::new();
-    visited.insert(start_pos);
-
-    while let Some(pos) = queue.pop_front() {
-        let (x, y) = pos;
-        let tile = map.get_tile(x, y).unwrap();
-        for direction in tile.get_directions() {
-            let other_pos = match direction {
-                Direction::North => (x, if y > 0 { y - 1 } else { continue }),
-                Direction::South => (x, y + 1),
-                Direction::West => (
-                    if x > 0 {
-                        x - 1
-                    } else {
-                        continue;
-                    },
-                    y,
-                ),
-                Direction::East => (x + 1, y),
-            };
-            if visited.contains(&other_pos) {
-                continue;
-            }
-            if let Some(other_tile) = map.get_adjacent_tile(pos, &direction) {
-                if other_tile.can_connect(&direction.get_opposite()) {
-                    visited.insert(other_pos);
-                    queue.push_back(other_pos);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Region {
+    Loop,
+    Inside,
+    Outside,
+}
+
+/// Classifies every tile by scanning each row and flipping between outside
+/// and inside whenever a loop pipe reaching north is crossed.
+fn classify(map: &Map, path: &[(usize, usize)]) -> Result<Vec<Vec<Region>>> {
+    let start_pipe = map.get_start_pipe_type()?;
+    let mut regions = map
+        .tiles
+        .iter()
+        .map(|row| vec![Region::Outside; row.len()])
+        .collect::<Vec<_>>();
+    for &(x, y) in path {
+        regions[y][x] = Region::Loop;
+    }
+    for (y, row) in regions.iter_mut().enumerate() {
+        let mut inside = false;
+        for (x, region) in row.iter_mut().enumerate() {
+            if *region == Region::Loop {
+                let pipe = match map.tiles[y][x] {
+                    Tile::Pipe(pipe) => pipe,
+                    _ => start_pipe,
+                };
+                if pipe.get_directions().contains(&Direction::North) {
+                    inside = !inside;
                 }
+            } else if inside {
+                *region = Region::Inside;
             }
         }
     }
-
-    visited.into_iter().collect()
+    Ok(regions)
 }
 
-pub fn solve_task_one(#[allow(unused_variables)] input: Vec<String>) -> Result<i32> {
-    let start_time = Instant::now();
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const BLUE: &str = "\x1b[34m";
+
+/// Draws the loop with box-drawing characters and marks every other tile `I`
+/// or `O`. When `color` is set the loop is bold with the start tile in
+/// yellow, inside is green, outside blue and pipes off the loop keep their
+/// box-drawing character, dimmed.
+pub fn render(input: Vec<String>, color: bool) -> Result<String> {
     let map = Map::try_from(input)?;
-    let start = map.get_start();
-    let longest_path = get_loop(&map, start).len();
-    eprintln!("Longest path: {}", longest_path);
-
-    eprintln!("{:?}", Instant::now() - start_time);
-    match longest_path % 2 {
-        0 => Ok((longest_path / 2) as i32),
-        _ => Ok((longest_path / 2 + 1) as i32),
-    }
-}
-
-fn find_outside_points(map: &Map) -> HashSet<(usize, usize)> {
-    let mut outside = HashSet::new();
-
-    for (y, row) in map.tiles.iter().enumerate() {
-        let mut indise = false;
-        let mut facing_up = None;
-        for (x, tile) in row.iter().enumerate() {
-            match tile {
-                Tile::Pipe(Pipe::NorthSouth) => {
-                    if facing_up != None {
-                        panic!("Invalid map 1");
-                    }
-                    indise = !indise;
-                }
-                Tile::Pipe(Pipe::WestEast) => {
-                    if facing_up == None {
-                        panic!("Invalid map 2");
-                    }
-                }
-                Tile::Pipe(Pipe::NorthEast) | Tile::Pipe(Pipe::SouthEast) => {
-                    if facing_up != None {
-                        panic!("Invalid map 3");
-                    }
-                    if let Tile::Pipe(Pipe::NorthEast) = tile {
-                        facing_up = Some(true);
-                    } else {
-                        facing_up = Some(false);
-                    }
-                }
-                Tile::Pipe(Pipe::NorthWest) | Tile::Pipe(Pipe::SouthWest) => {
-                    if facing_up == None {
-                        panic!("Invalid map 4");
-                    }
-                    if let Some(facing_up) = facing_up {
-                        if tile
-                            != &if facing_up {
-                                Tile::Pipe(Pipe::NorthWest)
-                            } else {
-                                Tile::Pipe(Pipe::SouthWest)
-                            }
-                        {
-                            indise = !indise;
-                        }
-                    }
-                    facing_up = None;
-                }
-                Tile::Ground => {}
-                _ => panic!("Unexpected character"),
-            }
-            if !indise {
-                outside.insert((x, y));
+    let path = get_loop(&map)?;
+    let start_pipe = map.get_start_pipe_type()?;
+    let regions = classify(&map, &path)?;
+
+    let mut out = String::new();
+    for (tiles, regions) in map.tiles.iter().zip(regions) {
+        for (tile, region) in tiles.iter().zip(regions) {
+            let c = match (tile, region) {
+                (Tile::Start, _) => start_pipe.box_char(),
+                (Tile::Pipe(pipe), Region::Loop) => pipe.box_char(),
+                // Without color junk pipes would look like the loop
+                (Tile::Pipe(_), Region::Inside) if !color => 'I',
+                (Tile::Pipe(_), _) if !color => 'O',
+                (Tile::Pipe(pipe), _) => pipe.box_char(),
+                (Tile::Ground, Region::Inside) => 'I',
+                (Tile::Ground, _) => 'O',
+            };
+            if !color {
+                out.push(c);
+                continue;
             }
+            let style = match (tile, region) {
+                (Tile::Start, _) => format!("{BOLD}{YELLOW}"),
+                (_, Region::Loop) => BOLD.to_string(),
+                (Tile::Pipe(_), Region::Inside) => format!("{DIM}{GREEN}"),
+                (Tile::Pipe(_), _) => format!("{DIM}{BLUE}"),
+                (_, Region::Inside) => GREEN.to_string(),
+                (_, _) => BLUE.to_string(),
+            };
+            out.push_str(&format!("{style}{c}{RESET}"));
         }
+        out.push('\n');
     }
+    Ok(out)
+}
 
-    outside
+fn loop_polygon(path: &[(usize, usize)]) -> Polygon {
+    Polygon::new(path.iter().map(|&(x, y)| (x as i64, y as i64)).collect())
 }
 
-pub fn solve_task_two(#[allow(unused_variables)] input: Vec<String>) -> Result<i32> {
+pub fn solve_task_one(input: Vec<String>) -> Result<Answer> {
     let start_time = Instant::now();
-    let mut map = Map::try_from(input)?;
-    let start = map.get_start();
-    let start_type = map.get_start_pipe_type();
-    let path = get_loop(&map, start);
-    for y in 0..map.tiles.len() {
-        for x in 0..map.tiles[y].len() {
-            if !path.contains(&(x, y)) {
-                map.update_tile((x, y), Tile::Ground);
-            }
-        }
-    }
-    map.update_tile(start, Tile::Pipe(start_type));
-    let map = map;
-    let outside = find_outside_points(&map);
+    let map = Map::try_from(input)?;
+    let path = get_loop(&map)?;
 
-    let map_size: i32 = (map.tiles.len() * map.tiles[0].len()).try_into().unwrap();
-    let path = HashSet::from_iter(path.iter().cloned());
+    eprintln!("{:?}", Instant::now() - start_time);
+    Ok((path.len() / 2).into())
+}
 
-    let sol = map_size - outside.union(&path).count() as i32;
+pub fn solve_task_two(input: Vec<String>) -> Result<Answer> {
+    let start_time = Instant::now();
+    let map = Map::try_from(input)?;
+    let path = get_loop(&map)?;
+    // Every tile of the loop is a boundary point of the polygon, so Pick's
+    // theorem counts exactly the enclosed tiles.
+    let sol = loop_polygon(&path).interior_points()?;
 
     eprintln!("{:?}", Instant::now() - start_time);
-    Ok(sol as i32)
+    Ok(sol.into())
 }
 
 #[cfg(test)]
@@ -381,7 +359,7 @@ mod test {
         Ok(lines?)
     }
 
-    use crate::{solve_task_one, solve_task_two};
+    use crate::{get_loop, render, solve_task_one, solve_task_two, Map, Pipe};
 
     #[test]
     fn test_case_one_example_1() -> Result<()> {
@@ -488,4 +466,48 @@ mod test {
             .unwrap()
             .can_connect(&crate::Direction::West));
     }
+
+    #[test]
+    fn test_start_at_edges() -> Result<()> {
+        let map = Map::try_from(vec![String::from("S7"), String::from("LJ")])?;
+        assert_eq!(map.get_start_pipe_type()?, Pipe::SouthEast);
+        assert_eq!(get_loop(&map)?, vec![(0, 0), (0, 1), (1, 1), (1, 0)]);
+
+        let map = Map::try_from(vec![String::from("F7"), String::from("LS")])?;
+        assert_eq!(map.get_start_pipe_type()?, Pipe::NorthWest);
+
+        let map = Map::try_from(vec![String::from("S-"), String::from("..")])?;
+        assert!(map.get_start_pipe_type().is_err());
+        assert!(get_loop(&map).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_render() -> Result<()> {
+        let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let file = get_file(cargo_manifest_dir.join("inputs/example_5.txt"))?;
+        assert_eq!(render(file, false)?, "OO┌┐O\nO┌┘│O\n┌┘I└┐\n│┌──┘\n└┘OOO\n");
+
+        for example in ["example_5.txt", "example_13.txt", "example_15.txt"] {
+            let file = get_file(cargo_manifest_dir.join("inputs").join(example))?;
+            let rendered = render(file.clone(), false)?;
+            assert_eq!(
+                solve_task_two(file)?,
+                rendered.chars().filter(|&c| c == 'I').count() as i64
+            );
+        }
+
+        // Junk pipes inside the loop are marked like the puzzle's walkthrough
+        let file = get_file(cargo_manifest_dir.join("inputs/example_15.txt"))?;
+        let marked = get_file(cargo_manifest_dir.join("inputs/example_16.txt"))?;
+        let rendered = render(file.clone(), false)?;
+        for (rendered, marked) in rendered.lines().zip(&marked) {
+            let inside = |line: &str| line.chars().map(|c| c == 'I').collect::<Vec<_>>();
+            assert_eq!(inside(rendered), inside(marked));
+        }
+
+        let file = get_file(cargo_manifest_dir.join("inputs/example_13.txt"))?;
+        assert!(render(file, true)?.contains("\x1b[1m\x1b[33m┌"));
+        Ok(())
+    }
 }
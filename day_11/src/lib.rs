@@ -4,13 +4,40 @@ use color_eyre::{
     eyre::{anyhow, Error},
     Result,
 };
+use common::answer::{Answer, Checked};
 
 #[derive(Debug)]
-struct Map {
+pub struct Map {
+    /// Galaxy positions as `(row, column)` in reading order
     galaxies: Vec<(usize, usize)>,
-    empty_rows: Vec<usize>,
-    empty_cols: Vec<usize>,
-    empty_mul_scale: i64,
+    /// Number of empty rows before each row index
+    empty_rows_before: Vec<usize>,
+    /// Number of empty columns before each column index
+    empty_cols_before: Vec<usize>,
+}
+
+/// Counts, for every index, the number of indices before it that are not occupied
+fn empty_prefix_sums(len: usize, occupied: impl Iterator<Item = usize>) -> Vec<usize> {
+    let mut is_occupied = vec![false; len];
+    occupied.for_each(|i| is_occupied[i] = true);
+    let mut prefix = Vec::with_capacity(len + 1);
+    prefix.push(0);
+    for occupied in is_occupied {
+        prefix.push(prefix.last().unwrap() + usize::from(!occupied));
+    }
+    prefix
+}
+
+/// Sum of `|a - b|` over every pair of values
+fn sum_of_pairwise_differences(mut values: Vec<i64>) -> Result<i64> {
+    values.sort_unstable();
+    let mut prefix_sum: i64 = 0;
+    let mut total: i64 = 0;
+    for (i, value) in values.into_iter().enumerate() {
+        total = total.try_add(value.try_mul(i as i64)?.try_sub(prefix_sum)?)?;
+        prefix_sum = prefix_sum.try_add(value)?;
+    }
+    Ok(total)
 }
 
 impl TryFrom<Vec<String>> for Map {
@@ -18,10 +45,10 @@ impl TryFrom<Vec<String>> for Map {
 
     fn try_from(value: Vec<String>) -> Result<Self, Self::Error> {
         let rows = value.len();
-        let columns = value[0].len();
+        let columns = value.first().map_or(0, |l| l.len());
         let mut galaxies = Vec::new();
         for (row, line) in value.into_iter().enumerate() {
-            for (col, c) in line.chars().into_iter().enumerate() {
+            for (col, c) in line.chars().enumerate() {
                 match c {
                     '.' => (),
                     '#' => galaxies.push((row, col)),
@@ -30,105 +57,63 @@ impl TryFrom<Vec<String>> for Map {
                 }
             }
         }
-        let galaxy_rows = &galaxies.iter().map(|(g_r, _)| g_r).collect::<Vec<_>>();
-        let empty_rows = (0..rows)
-            .into_iter()
-            .filter(|r| !galaxy_rows.contains(&r))
-            .collect::<Vec<_>>();
-
-        let galaxy_cols = &galaxies.iter().map(|(_, g_c)| g_c).collect::<Vec<_>>();
-        let empty_cols = (0..columns)
-            .into_iter()
-            .filter(|c| !galaxy_cols.contains(&c))
-            .collect::<Vec<_>>();
+        let empty_rows_before = empty_prefix_sums(rows, galaxies.iter().map(|(r, _)| *r));
+        let empty_cols_before = empty_prefix_sums(columns, galaxies.iter().map(|(_, c)| *c));
 
         Ok(Map {
             galaxies,
-            empty_cols,
-            empty_rows,
-            empty_mul_scale: 1,
+            empty_rows_before,
+            empty_cols_before,
         })
     }
 }
 
 impl Map {
-    fn get_galaxy_pairings(&self) -> Vec<((usize, usize), (usize, usize))> {
-        self.galaxies
-            .iter()
-            .enumerate()
-            .flat_map(|(i, g1)| {
-                self.galaxies
-                    .clone()
-                    .into_iter()
-                    .enumerate()
-                    .skip(i + 1)
-                    .map(move |(_, g2)| (*g1, g2))
-            })
-            .collect()
+    /// Position of a galaxy after every empty row and column grows to
+    /// `empty_mul_scale` rows and columns
+    pub fn expanded(&self, galaxy: (usize, usize), empty_mul_scale: i64) -> (i64, i64) {
+        let (row, col) = galaxy;
+        (
+            row as i64 + self.empty_rows_before[row] as i64 * (empty_mul_scale - 1),
+            col as i64 + self.empty_cols_before[col] as i64 * (empty_mul_scale - 1),
+        )
     }
 
-    fn set_empty_mul_scale(&mut self, empty_mul_scale: i64) {
-        self.empty_mul_scale = empty_mul_scale;
+    /// Expanded distance between the `a`th and `b`th galaxy in reading order
+    pub fn get_distance(&self, a: usize, b: usize, empty_mul_scale: i64) -> Option<i64> {
+        let (r1, c1) = self.expanded(*self.galaxies.get(a)?, empty_mul_scale);
+        let (r2, c2) = self.expanded(*self.galaxies.get(b)?, empty_mul_scale);
+        Some((r1 - r2).abs() + (c1 - c2).abs())
     }
 
-    fn get_total_distances(&self) -> i64 {
-        let pairings = self.get_galaxy_pairings();
-        let distances: Vec<_> = pairings
+    /// Sum of the expanded distances between every pair of galaxies. The
+    /// Manhattan distance splits by axis, so each axis is summed on its own.
+    pub fn get_total_distances(&self, empty_mul_scale: i64) -> Result<i64> {
+        let (rows, cols) = self
+            .galaxies
             .iter()
-            .map(|(g1, g2)| {
-                let (r1, c1) = g1;
-                let (r2, c2) = g2;
-                let empty_rows_between = self
-                    .empty_rows
-                    .iter()
-                    .filter(|&r| {
-                        if r1 <= r2 {
-                            (r1..r2).contains(&r)
-                        } else {
-                            (r2..r1).contains(&r)
-                        }
-                    })
-                    .count() as i64;
-                let empty_cols_between = self
-                    .empty_cols
-                    .iter()
-                    .filter(|&c| {
-                        if c1 <= c2 {
-                            (c1..c2).contains(&c)
-                        } else {
-                            (c2..c1).contains(&c)
-                        }
-                    })
-                    .count() as i64;
-
-                let r = (*r1 as i64 - *r2 as i64).abs()
-                    + empty_rows_between * (self.empty_mul_scale - 1);
-                let c = (*c1 as i64 - *c2 as i64).abs()
-                    + empty_cols_between * (self.empty_mul_scale - 1);
-                r + c
-            })
-            .collect();
-        distances.iter().sum()
+            .map(|g| self.expanded(*g, empty_mul_scale))
+            .unzip();
+        sum_of_pairwise_differences(rows)?.try_add(sum_of_pairwise_differences(cols)?)
     }
 }
-pub fn solve_task_one(#[allow(unused_variables)] input: Vec<String>) -> Result<i64> {
+
+pub fn solve_task_one(input: Vec<String>) -> Result<Answer> {
     let start_time = Instant::now();
-    let mut map = Map::try_from(input)?;
-    map.set_empty_mul_scale(2);
+    let map = Map::try_from(input)?;
 
-    let distances = map.get_total_distances();
+    let distances = map.get_total_distances(2)?;
     eprintln!("{:?}", Instant::now() - start_time);
-    Ok(distances)
+    Ok(distances.into())
 }
 
-pub fn solve_task_two(#[allow(unused_variables)] input: Vec<String>) -> Result<i64> {
+pub fn solve_task_two(input: Vec<String>) -> Result<Answer> {
     let start_time = Instant::now();
 
-    let mut map = Map::try_from(input)?;
-    map.set_empty_mul_scale(1_000_000);
-    let distances = map.get_total_distances();
+    let map = Map::try_from(input)?;
+    let distances = map.get_total_distances(1_000_000)?;
     eprintln!("{:?}", Instant::now() - start_time);
-    Ok(distances)
+    Ok(distances.into())
 }
 
 #[cfg(test)]
@@ -148,7 +133,7 @@ mod test {
         Ok(lines?)
     }
 
-    use crate::{solve_task_one, solve_task_two};
+    use crate::{solve_task_one, solve_task_two, Map};
 
     #[test]
     fn test_case_one_example() -> Result<()> {
@@ -183,4 +168,18 @@ mod test {
         assert_eq!(solve_task_two(file)?, 742305960572);
         Ok(())
     }
+
+    #[test]
+    fn test_distances() -> Result<()> {
+        let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let map = Map::try_from(get_file(cargo_manifest_dir.join("inputs/example_1.txt"))?)?;
+        assert_eq!(map.get_distance(4, 8, 2), Some(9));
+        assert_eq!(map.get_distance(0, 6, 2), Some(15));
+        assert_eq!(map.get_distance(2, 5, 2), Some(17));
+        assert_eq!(map.get_distance(7, 8, 2), Some(5));
+        assert_eq!(map.get_distance(0, 9, 2), None);
+        assert_eq!(map.get_total_distances(10)?, 1030);
+        assert_eq!(map.get_total_distances(100)?, 8410);
+        Ok(())
+    }
 }
@@ -1,14 +1,24 @@
-use std::path::PathBuf;
+use std::num::NonZeroUsize;
 
+use clap::Parser;
 use color_eyre::Result;
 
-use common::get_file;
+use common::CommonCli;
+
+#[derive(Parser)]
+struct Args {
+    #[command(flatten)]
+    input: CommonCli,
+    /// How many copies of each row are joined together
+    #[arg(short, long, default_value_t = NonZeroUsize::new(5).unwrap())]
+    unfold: NonZeroUsize,
+}
 
 fn main() -> Result<()> {
     color_eyre::install()?;
-    let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-    let file = get_file(cargo_manifest_dir.join("inputs/full.txt"))?;
-    let sol = solution::solve_task_two(file)?;
+    let args = Args::parse();
+    let file = args.input.read_input(env!("CARGO_MANIFEST_DIR"))?;
+    let sol = solution::solve_task_two(file, args.unfold.get())?;
     println!("{sol}");
     Ok(())
 }
@@ -1,9 +1,10 @@
-use std::{collections::HashMap, str::FromStr, time::Instant};
+use std::{fmt::Display, str::FromStr, time::Instant};
 
 use color_eyre::{eyre::anyhow, Result};
+use common::answer::{Answer, Checked, CheckedIterator};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
-enum HotSpringState {
+pub enum HotSpringState {
     Operating,
     Damaged,
     Unknown,
@@ -17,13 +18,23 @@ impl TryFrom<char> for HotSpringState {
             '.' => Ok(HotSpringState::Operating),
             '#' => Ok(HotSpringState::Damaged),
             '?' => Ok(HotSpringState::Unknown),
-            _ => Err(anyhow!("Invalid character")),
+            _ => Err(anyhow!("Invalid character {value}")),
+        }
+    }
+}
+
+impl From<HotSpringState> for char {
+    fn from(value: HotSpringState) -> Self {
+        match value {
+            HotSpringState::Operating => '.',
+            HotSpringState::Damaged => '#',
+            HotSpringState::Unknown => '?',
         }
     }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
-struct HotSpringRow {
+pub struct HotSpringRow {
     states: Vec<HotSpringState>,
     broken_spans: Vec<usize>,
 }
@@ -32,131 +43,239 @@ impl FromStr for HotSpringRow {
     type Err = color_eyre::Report;
 
     fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
-        let mut parts = value.split_ascii_whitespace();
-        let states: Result<Vec<HotSpringState>> = parts
-            .next()
-            .unwrap()
+        let (states, broken_spans) = value
+            .split_once(' ')
+            .ok_or_else(|| anyhow!("Invalid row {value}"))?;
+        let states = states
+            .trim()
             .chars()
-            .into_iter()
             .map(HotSpringState::try_from)
-            .collect();
-
-        let broken_spans: Vec<usize> = parts
-            .next()
-            .unwrap()
+            .collect::<Result<Vec<_>>>()?;
+        let broken_spans = broken_spans
+            .trim()
             .split(',')
-            .map(|n| n.parse().unwrap())
-            .collect();
+            .map(|n| match n.parse() {
+                Ok(0) | Err(_) => Err(anyhow!("Invalid span {n} in {value}")),
+                Ok(span) => Ok(span),
+            })
+            .collect::<Result<Vec<usize>>>()?;
 
         Ok(Self {
-            states: states?,
+            states,
             broken_spans,
         })
     }
 }
 
-fn count_solutions(row: HotSpringRow, cache: &mut HashMap<HotSpringRow, i64>) -> i64 {
-    if row.states.is_empty() {
-        return if row.broken_spans.is_empty() { 1 } else { 0 };
+impl Display for HotSpringRow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let states: String = self.states.iter().map(|&s| char::from(s)).collect();
+        let spans: Vec<String> = self.broken_spans.iter().map(|s| s.to_string()).collect();
+        write!(f, "{states} {}", spans.join(","))
     }
-    if row.broken_spans.is_empty() {
-        return if row.states.contains(&HotSpringState::Damaged) {
-            0
-        } else {
-            1
-        };
+}
+
+impl HotSpringRow {
+    /// Repeats the states `factor` times joined by unknown springs, and the
+    /// spans `factor` times. A factor of 0 would leave no row at all.
+    pub fn unfold(&self, factor: usize) -> Result<Self> {
+        if factor == 0 {
+            return Err(anyhow!("Can't unfold {self} zero times"));
+        }
+        let mut states = Vec::with_capacity((self.states.len() + 1) * factor);
+        for i in 0..factor {
+            if i > 0 {
+                states.push(HotSpringState::Unknown);
+            }
+            states.extend_from_slice(&self.states);
+        }
+        Ok(Self {
+            states,
+            broken_spans: self.broken_spans.repeat(factor),
+        })
     }
 
-    if let Some(&cached) = cache.get(&row) {
-        return cached;
+    pub fn count_arrangements(&self) -> Result<u64> {
+        ArrangementCounter::new().count(self)
     }
 
-    let mut result = 0;
-    if &HotSpringState::Damaged != row.states.first().unwrap() {
-        let mut new_row = row.clone();
-        new_row.states = new_row.states[1..].to_vec();
-        result += count_solutions(new_row, cache);
+    /// Every way to replace the unknown springs that matches the spans. Meant
+    /// for small rows, there are as many as [`Self::count_arrangements`].
+    pub fn arrangements(&self) -> Result<Arrangements<'_>> {
+        let mut counter = ArrangementCounter::new();
+        counter.fill(self)?;
+        let mut stack = vec![];
+        if counter.ways(0, 0) > 0 {
+            stack.push((0, 0, None));
+        }
+        Ok(Arrangements {
+            row: self,
+            ways: counter,
+            stack,
+            current: Vec::with_capacity(self.states.len()),
+        })
     }
-    let first_span = row.broken_spans[0];
-    if row.states.len() >= first_span
-        && !row.states[..first_span].contains(&HotSpringState::Operating)
-    {
-        if row.states.len() == first_span {
-            let mut new_row = row.clone();
-            new_row.broken_spans = new_row.broken_spans[1..].to_vec();
-            new_row.states = vec![];
-            result += count_solutions(new_row, cache);
-        } else if row.states[first_span] != HotSpringState::Damaged {
-            let mut new_row = row.clone();
-            new_row.broken_spans = new_row.broken_spans[1..].to_vec();
-            new_row.states = row.states[(first_span + 1)..].to_vec();
-            result += count_solutions(new_row, cache);
+}
+
+/// Counts the arrangements of rows bottom up. The table is kept between
+/// rows, so counting many rows with one counter only allocates when a row
+/// is larger than all before it.
+#[derive(Debug, Default, Clone)]
+pub struct ArrangementCounter {
+    /// `ways[i * (spans + 1) + j]` arrangements of the states from `i` on
+    /// with the spans from `j` on, for `i` up to `states + 1`
+    ways: Vec<u64>,
+    spans: usize,
+    /// Operating springs before each position
+    operating: Vec<usize>,
+}
+
+impl ArrangementCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn count(&mut self, row: &HotSpringRow) -> Result<u64> {
+        self.fill(row)?;
+        Ok(self.ways(0, 0))
+    }
+
+    fn ways(&self, position: usize, span: usize) -> u64 {
+        self.ways[position * (self.spans + 1) + span]
+    }
+
+    /// Whether span `span` can start at `position`, followed by an operating
+    /// spring or the end of the row
+    fn fits(row: &HotSpringRow, operating: &[usize], position: usize, span: usize) -> bool {
+        let end = position + row.broken_spans[span];
+        end <= row.states.len()
+            && operating[end] == operating[position]
+            && row.states.get(end) != Some(&HotSpringState::Damaged)
+    }
+
+    fn fill(&mut self, row: &HotSpringRow) -> Result<()> {
+        let n = row.states.len();
+        let m = row.broken_spans.len();
+        self.spans = m;
+        self.operating.clear();
+        self.operating.push(0);
+        for state in &row.states {
+            let before = *self.operating.last().unwrap_or(&0);
+            self.operating
+                .push(before + usize::from(*state == HotSpringState::Operating));
+        }
+        self.ways.clear();
+        self.ways.resize((n + 2) * (m + 1), 0);
+        // Past the end only no more spans fit, a span ending at the last
+        // spring continues one further
+        self.ways[n * (m + 1) + m] = 1;
+        self.ways[(n + 1) * (m + 1) + m] = 1;
+        for i in (0..n).rev() {
+            for j in 0..=m {
+                let mut ways = 0;
+                if row.states[i] != HotSpringState::Damaged {
+                    ways = self.ways(i + 1, j);
+                }
+                if j < m && Self::fits(row, &self.operating, i, j) {
+                    ways = ways.try_add(self.ways(i + row.broken_spans[j] + 1, j + 1))?;
+                }
+                self.ways[i * (m + 1) + j] = ways;
+            }
         }
+        Ok(())
     }
-    cache.insert(row, result);
-    result
 }
 
-pub fn solve_task_one(#[allow(unused_variables)] input: Vec<String>) -> Result<i64> {
-    let start_time = Instant::now();
-    let mut cache = HashMap::new();
-    let rows = input
-        .into_iter()
-        .map(|line| line.parse::<HotSpringRow>().unwrap())
-        .collect::<Vec<HotSpringRow>>();
-    let totals: Vec<i64> = rows
-        .into_iter()
-        .map(|el| count_solutions(el, &mut cache))
-        .collect();
-    let total = totals.iter().sum();
-    eprintln!("{:?}", Instant::now() - start_time);
-    Ok(total)
+/// How an arrangement continues from the position before
+#[derive(Debug, Clone, Copy)]
+enum Step {
+    Operating,
+    Span,
 }
 
-pub fn solve_task_two(#[allow(unused_variables)] input: Vec<String>) -> Result<i64> {
-    let start_time = Instant::now();
-    let mut cache = HashMap::new();
+/// Iterator over the arrangements of a row, see [`HotSpringRow::arrangements`]
+#[derive(Debug)]
+pub struct Arrangements<'a> {
+    row: &'a HotSpringRow,
+    ways: ArrangementCounter,
+    /// Position and span to continue from, with the step that led there
+    stack: Vec<(usize, usize, Option<Step>)>,
+    current: Vec<HotSpringState>,
+}
 
-    let input = input
-        .iter()
-        .map(|line| {
-            let mut parts = line.split_ascii_whitespace();
-            let states = parts.next().unwrap();
-            let broken_spans = parts.next().unwrap();
-            let unpacked_states = [states; 5].into_iter().fold("".to_string(), |acc, s| {
-                if acc.is_empty() {
-                    acc + s
-                } else {
-                    acc + "?" + s
+impl Iterator for Arrangements<'_> {
+    type Item = Vec<HotSpringState>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let n = self.row.states.len();
+        let m = self.row.broken_spans.len();
+        while let Some((i, j, step)) = self.stack.pop() {
+            // The step led from the previous position to `i`, undo deeper choices first
+            match step {
+                None => self.current.clear(),
+                Some(Step::Operating) => {
+                    self.current.truncate(i - 1);
+                    self.current.push(HotSpringState::Operating);
                 }
-            });
-            let unpacked_brocken_spans =
-                [broken_spans; 5]
-                    .into_iter()
-                    .fold("".to_string(), |acc, s| {
-                        if !acc.is_empty() {
-                            acc + "," + s
-                        } else {
-                            acc + s
-                        }
-                    });
-
-            format!("{} {}", unpacked_states, unpacked_brocken_spans)
-        })
-        .collect::<Vec<String>>();
+                Some(Step::Span) => {
+                    let span = self.row.broken_spans[j - 1];
+                    self.current.truncate(i - span - 1);
+                    self.current
+                        .extend(std::iter::repeat_n(HotSpringState::Damaged, span));
+                    if i <= n {
+                        self.current.push(HotSpringState::Operating);
+                    }
+                }
+            }
+            if i >= n {
+                // Only reached with every span placed, the table rules out the rest
+                return Some(self.current.clone());
+            }
+            if self.row.states[i] != HotSpringState::Damaged && self.ways.ways(i + 1, j) > 0 {
+                self.stack.push((i + 1, j, Some(Step::Operating)));
+            }
+            if j < m && ArrangementCounter::fits(self.row, &self.ways.operating, i, j) {
+                let next = i + self.row.broken_spans[j] + 1;
+                if self.ways.ways(next, j + 1) > 0 {
+                    self.stack.push((next, j + 1, Some(Step::Span)));
+                }
+            }
+        }
+        None
+    }
+}
 
-    let rows = input
-        .into_iter()
-        .map(|line| line.parse::<HotSpringRow>().unwrap())
-        .collect::<Vec<HotSpringRow>>();
-    let totals: Vec<i64> = rows
+fn parse_rows(input: &[String]) -> Result<Vec<HotSpringRow>> {
+    input
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.parse())
+        .collect()
+}
+
+/// Sum of the arrangements of every row unfolded `factor` times
+pub fn total_arrangements(input: &[String], factor: usize) -> Result<u64> {
+    let mut counter = ArrangementCounter::new();
+    parse_rows(input)?
+        .iter()
+        .map(|row| counter.count(&row.unfold(factor)?))
+        .collect::<Result<Vec<_>>>()?
         .into_iter()
-        .map(|el| count_solutions(el, &mut cache))
-        .collect();
-    let total = totals.iter().sum();
+        .try_sum()
+}
+
+pub fn solve_task_one(input: Vec<String>) -> Result<Answer> {
+    let start_time = Instant::now();
+    let total = total_arrangements(&input, 1)?;
+    eprintln!("{:?}", Instant::now() - start_time);
+    Ok(total.into())
+}
 
+pub fn solve_task_two(input: Vec<String>, factor: usize) -> Result<Answer> {
+    let start_time = Instant::now();
+    let total = total_arrangements(&input, factor)?;
     eprintln!("{:?}", Instant::now() - start_time);
-    Ok(total)
+    Ok(total.into())
 }
 
 #[cfg(test)]
@@ -176,7 +295,7 @@ mod test {
         Ok(lines?)
     }
 
-    use crate::{solve_task_one, solve_task_two};
+    use crate::{solve_task_one, solve_task_two, HotSpringRow};
 
     #[test]
     fn test_case_one_example() -> Result<()> {
@@ -200,7 +319,7 @@ mod test {
     fn test_case_two_example() -> Result<()> {
         let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         let file = get_file(cargo_manifest_dir.join("inputs/example_2.txt"))?;
-        assert_eq!(solve_task_two(file)?, 525152);
+        assert_eq!(solve_task_two(file, 5)?, 525152);
         Ok(())
     }
 
@@ -208,7 +327,31 @@ mod test {
     fn test_case_two_solve() -> Result<()> {
         let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         let file = get_file(cargo_manifest_dir.join("inputs/full.txt"))?;
-        assert_eq!(solve_task_two(file)?, 4964259839627);
+        assert_eq!(solve_task_two(file, 5)?, 4964259839627);
+        Ok(())
+    }
+
+    #[test]
+    fn test_arrangements() -> Result<()> {
+        let row: HotSpringRow = "?###???????? 3,2,1".parse()?;
+        assert_eq!(row.count_arrangements()?, 10);
+        let arrangements: Vec<String> = row
+            .arrangements()?
+            .map(|states| states.into_iter().map(char::from).collect())
+            .collect();
+        assert_eq!(arrangements.len(), 10);
+        assert!(arrangements.contains(&".###.##.#...".to_string()));
+        assert!(arrangements.contains(&".###....##.#".to_string()));
+        for arrangement in &arrangements {
+            let known: HotSpringRow = format!("{arrangement} 3,2,1").parse()?;
+            assert_eq!(known.count_arrangements()?, 1);
+        }
+
+        let unfolded = "???.### 1,1,3".parse::<HotSpringRow>()?.unfold(2)?;
+        assert_eq!(unfolded.to_string(), "???.###????.### 1,1,3,1,1,3");
+        assert_eq!(unfolded.count_arrangements()?, 1);
+        assert!(row.unfold(0).is_err());
+        assert!("??? 1,0".parse::<HotSpringRow>().is_err());
         Ok(())
     }
 }
@@ -1,149 +1,162 @@
 use std::time::Instant;
 
-use color_eyre::Result;
-
-pub fn find_hor_reflections(pattern: Vec<String>) -> Vec<usize> {
-    let mut reflections = vec![];
-    for i in 1..pattern.len() {
-        let lines_above = i;
-        let lines_below = pattern.len() - i;
-        let reflection_size = if lines_above > lines_below {
-            lines_below
-        } else {
-            lines_above
-        };
-        let mut bottom_part = pattern[i..i + reflection_size].to_vec();
-        bottom_part.reverse();
-        if pattern[i - reflection_size..i] == bottom_part {
-            reflections.push(i * 100);
-            break;
-        }
-    }
-    reflections
+use color_eyre::{eyre::anyhow, Result};
+use common::answer::{Answer, CheckedIterator};
+
+/// A pattern of ash (`.`) and rocks (`#`) as bitmasks, bit `c` of row `r`
+/// is set for a rock in column `c`, and so is bit `r` of column `c`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    rows: Vec<u64>,
+    cols: Vec<u64>,
 }
 
-pub fn find_ver_reflections(pattern: Vec<String>) -> Vec<usize> {
-    let mut reflections = vec![];
-    let width = pattern[0].len();
-    for i in 1..width {
-        let bars_left = i;
-        let bars_right = width - i;
-        let reflection_size = if bars_left > bars_right {
-            bars_right
-        } else {
-            bars_left
-        };
-        let left_part = pattern
-            .iter()
-            .map(|l| l[i - reflection_size..i].to_string())
-            .collect::<Vec<String>>();
-        let right_part = pattern
-            .iter()
-            .map(|l| l[i..i + reflection_size].chars().rev().collect::<String>())
-            .collect::<Vec<String>>();
-
-        if left_part == right_part {
-            reflections.push(i);
+impl Pattern {
+    pub fn from_lines(lines: &[String]) -> Result<Self> {
+        let width = lines.first().map_or(0, |line| line.len());
+        if width == 0 || width > 64 || lines.len() > 64 {
+            return Err(anyhow!("Patterns have to be 1 to 64 wide and high"));
+        }
+        let mut rows = vec![0; lines.len()];
+        let mut cols = vec![0; width];
+        for (r, line) in lines.iter().enumerate() {
+            if line.len() != width {
+                return Err(anyhow!("Line {line} is not {width} wide"));
+            }
+            for (c, char) in line.chars().enumerate() {
+                match char {
+                    '.' => {}
+                    '#' => {
+                        rows[r] |= 1 << c;
+                        cols[c] |= 1 << r;
+                    }
+                    _ => return Err(anyhow!("Invalid character {char} in {line}")),
+                }
+            }
         }
+        Ok(Self { rows, cols })
     }
-    reflections
-}
 
-pub fn solve_task_one(#[allow(unused_variables)] input: Vec<String>) -> Result<i32> {
-    let start_time = Instant::now();
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
 
-    let patterns: Vec<Vec<String>> = input.split(|x| x == "").map(|el| el.to_vec()).collect();
-    // eprintln!("{:?}", patterns.len());
-    let sol: usize = patterns
-        .iter()
-        .map(|pattern| {
-            let hor_reflections = find_hor_reflections(pattern.to_vec());
-            let ver_reflections = find_ver_reflections(pattern.to_vec());
+    pub fn width(&self) -> usize {
+        self.cols.len()
+    }
+}
 
-            hor_reflections.get(0).unwrap_or(&0) + ver_reflections.get(0).unwrap_or(&0)
-        })
-        .sum();
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    /// A mirror between two rows
+    Horizontal,
+    /// A mirror between two columns
+    Vertical,
+}
 
-    eprintln!("{:?}", Instant::now() - start_time);
+/// A cell that differs from its mirror image, fixing either one makes them match
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Smudge {
+    /// Row and column on the top or left side of the mirror
+    pub cell: (usize, usize),
+    /// Row and column of its mirror image
+    pub image: (usize, usize),
+}
 
-    Ok(sol as i32)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reflection {
+    pub axis: Axis,
+    /// Rows above or columns left of the mirror
+    pub position: usize,
+    pub smudges: Vec<Smudge>,
 }
 
-pub fn find_smudged_hor_reflections(pattern: Vec<String>) -> Vec<usize> {
-    let mut reflections = vec![];
-    for i in 1..pattern.len() {
-        let lines_above = i;
-        let lines_below = pattern.len() - i;
-        let reflection_size = if lines_above > lines_below {
-            lines_below
-        } else {
-            lines_above
-        };
-        let mut bottom_part = pattern[i..i + reflection_size].to_vec();
-        bottom_part.reverse();
-        let differences: usize = bottom_part
-            .iter()
-            .zip(pattern[i - reflection_size..i].iter())
-            .map(|(a, b)| a.chars().zip(b.chars()).filter(|(a, b)| a != b).count())
-            .sum();
-
-        if differences == 1 {
-            reflections.push(i * 100);
-            break;
+impl Reflection {
+    pub fn summary(&self) -> usize {
+        match self.axis {
+            Axis::Horizontal => self.position * 100,
+            Axis::Vertical => self.position,
         }
     }
-    reflections
 }
 
-pub fn find_smudged_ver_reflections(pattern: Vec<String>) -> Vec<usize> {
-    let mut reflections = vec![];
-    let width = pattern[0].len();
-    for i in 1..width {
-        let bars_left = i;
-        let bars_right = width - i;
-        let reflection_size = if bars_left > bars_right {
-            bars_right
-        } else {
-            bars_left
-        };
-        let left_part = pattern
-            .iter()
-            .map(|l| l[i - reflection_size..i].to_string())
-            .collect::<Vec<String>>();
-        let right_part = pattern
-            .iter()
-            .map(|l| l[i..i + reflection_size].chars().rev().collect::<String>())
-            .collect::<Vec<String>>();
-        let differences: usize = left_part
-            .iter()
-            .zip(right_part.iter())
-            .map(|(a, b)| a.chars().zip(b.chars()).filter(|(a, b)| a != b).count())
-            .sum();
-        if differences == 1 {
-            reflections.push(i);
-        }
-    }
+/// Mirrors between the rows or columns in `lines` that hold with exactly
+/// `smudges` cells fixed
+fn find_mirrors(lines: &[u64], axis: Axis, smudges: usize) -> Vec<Reflection> {
+    (1..lines.len())
+        .filter_map(|position| {
+            let pairs = (0..position.min(lines.len() - position))
+                .map(|offset| (position - 1 - offset, position + offset));
+            let mismatches: usize = pairs
+                .clone()
+                .map(|(a, b)| (lines[a] ^ lines[b]).count_ones() as usize)
+                .sum();
+            if mismatches != smudges {
+                return None;
+            }
+            let smudges = pairs
+                .flat_map(|(a, b)| {
+                    let diff = lines[a] ^ lines[b];
+                    (0..64)
+                        .filter(move |bit| diff & (1 << bit) != 0)
+                        .map(move |bit| match axis {
+                            Axis::Horizontal => Smudge {
+                                cell: (a, bit),
+                                image: (b, bit),
+                            },
+                            Axis::Vertical => Smudge {
+                                cell: (bit, a),
+                                image: (bit, b),
+                            },
+                        })
+                })
+                .collect();
+            Some(Reflection {
+                axis,
+                position,
+                smudges,
+            })
+        })
+        .collect()
+}
+
+/// Every horizontal and then vertical mirror that holds with exactly
+/// `smudges` cells fixed
+pub fn find_reflections(pattern: &Pattern, smudges: usize) -> Vec<Reflection> {
+    let mut reflections = find_mirrors(&pattern.rows, Axis::Horizontal, smudges);
+    reflections.extend(find_mirrors(&pattern.cols, Axis::Vertical, smudges));
     reflections
 }
 
-pub fn solve_task_two(#[allow(unused_variables)] input: Vec<String>) -> Result<i32> {
-    let start_time = Instant::now();
-    let patterns: Vec<Vec<String>> = input.split(|x| x == "").map(|el| el.to_vec()).collect();
-    let sol: usize = patterns
-        .iter()
-        .map(|pattern| {
-            let hor_reflections = find_smudged_hor_reflections(pattern.to_vec());
-            let ver_reflections = find_smudged_ver_reflections(pattern.to_vec());
-            if hor_reflections.len() + ver_reflections.len() != 1 {
-                eprintln!("{:#?}", pattern);
-                eprintln!("{:?}", hor_reflections);
-                eprintln!("{:?}", ver_reflections);
-            }
-            hor_reflections.get(0).unwrap_or(&0) + ver_reflections.get(0).unwrap_or(&0)
+/// Sum of the summaries of the first reflection of every pattern
+pub fn summarize(input: &[String], smudges: usize) -> Result<usize> {
+    input
+        .split(|line| line.is_empty())
+        .filter(|lines| !lines.is_empty())
+        .map(|lines| {
+            let pattern = Pattern::from_lines(lines)?;
+            find_reflections(&pattern, smudges)
+                .first()
+                .map(Reflection::summary)
+                .ok_or_else(|| anyhow!("No reflection with {smudges} smudges in {lines:?}"))
         })
-        .sum();
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .try_sum()
+}
+
+pub fn solve_task_one(input: Vec<String>) -> Result<Answer> {
+    let start_time = Instant::now();
+    let sol = summarize(&input, 0)?;
+    eprintln!("{:?}", Instant::now() - start_time);
+    Ok(sol.into())
+}
+
+pub fn solve_task_two(input: Vec<String>) -> Result<Answer> {
+    let start_time = Instant::now();
+    let sol = summarize(&input, 1)?;
     eprintln!("{:?}", Instant::now() - start_time);
-    Ok(sol as i32)
+    Ok(sol.into())
 }
 
 #[cfg(test)]
@@ -163,7 +176,7 @@ mod test {
         Ok(lines?)
     }
 
-    use crate::{solve_task_one, solve_task_two};
+    use crate::{find_reflections, solve_task_one, solve_task_two, Axis, Pattern, Smudge};
 
     #[test]
     fn test_case_one_example() -> Result<()> {
@@ -198,4 +211,30 @@ mod test {
         assert_eq!(solve_task_two(file)?, 33183);
         Ok(())
     }
+
+    #[test]
+    fn test_find_reflections() -> Result<()> {
+        let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let file = get_file(cargo_manifest_dir.join("inputs/example_1.txt"))?;
+        let first = Pattern::from_lines(file.split(|l| l.is_empty()).next().unwrap())?;
+        assert_eq!((first.width(), first.height()), (9, 7));
+
+        let clean = find_reflections(&first, 0);
+        assert_eq!(clean.len(), 1);
+        assert_eq!((clean[0].axis, clean[0].position), (Axis::Vertical, 5));
+        assert!(clean[0].smudges.is_empty());
+
+        let smudged = find_reflections(&first, 1);
+        assert_eq!(smudged.len(), 1);
+        assert_eq!(smudged[0].summary(), 300);
+        assert_eq!(
+            smudged[0].smudges,
+            [Smudge {
+                cell: (0, 0),
+                image: (5, 0)
+            }]
+        );
+        assert!(Pattern::from_lines(&["#.".to_string(), "#".to_string()]).is_err());
+        Ok(())
+    }
 }
@@ -1,13 +1,12 @@
-use std::path::PathBuf;
-
+use clap::Parser;
 use color_eyre::Result;
 
-use common::get_file;
+use common::CommonCli;
 
 fn main() -> Result<()> {
     color_eyre::install()?;
-    let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-    let file = get_file(cargo_manifest_dir.join("inputs/full.txt"))?;
+    let args = CommonCli::parse();
+    let file = args.read_input(env!("CARGO_MANIFEST_DIR"))?;
     let sol = solution::solve_task_two(file)?;
     println!("{sol}");
     Ok(())
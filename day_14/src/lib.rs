@@ -1,6 +1,7 @@
 use std::time::Instant;
 
 use color_eyre::Result;
+use common::{answer::Answer, cycle::find_cycle_from};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum Tile {
@@ -26,45 +27,115 @@ enum Direction {
     Left,
     Right,
 }
+/// A grid of booleans packed into 64 bit words, one word run per row.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct BitGrid {
+    words_per_row: usize,
+    bits: Vec<u64>,
+}
+
+impl BitGrid {
+    fn new(width: usize, height: usize) -> Self {
+        let words_per_row = width.div_ceil(64);
+        Self {
+            words_per_row,
+            bits: vec![0; words_per_row * height],
+        }
+    }
+
+    fn get(&self, x: usize, y: usize) -> bool {
+        self.bits[y * self.words_per_row + x / 64] & (1 << (x % 64)) != 0
+    }
+
+    fn set(&mut self, x: usize, y: usize, value: bool) {
+        let word = &mut self.bits[y * self.words_per_row + x / 64];
+        if value {
+            *word |= 1 << (x % 64);
+        } else {
+            *word &= !(1 << (x % 64));
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct Map {
-    tiles: Vec<Vec<Tile>>,
+    width: usize,
+    height: usize,
+    squares: BitGrid,
+    rounds: BitGrid,
 }
 
 impl Map {
     fn from_lines(lines: Vec<String>) -> Self {
-        let tiles = lines
-            .iter()
-            .map(|line| line.chars().map(Tile::from_char).collect())
-            .collect();
-        Self { tiles }
+        let height = lines.len();
+        let width = lines.first().map_or(0, |l| l.len());
+        let mut squares = BitGrid::new(width, height);
+        let mut rounds = BitGrid::new(width, height);
+        for (y, line) in lines.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                match Tile::from_char(c) {
+                    Tile::Empty => {}
+                    Tile::Square => squares.set(x, y, true),
+                    Tile::Round => rounds.set(x, y, true),
+                }
+            }
+        }
+        Self {
+            width,
+            height,
+            squares,
+            rounds,
+        }
     }
 
-    fn get(&self, x: usize, y: usize) -> Option<&Tile> {
-        self.tiles.get(y).and_then(|row| row.get(x))
+    fn get(&self, x: usize, y: usize) -> Tile {
+        if self.squares.get(x, y) {
+            Tile::Square
+        } else if self.rounds.get(x, y) {
+            Tile::Round
+        } else {
+            Tile::Empty
+        }
     }
 
-    fn set(&mut self, x: usize, y: usize, tile: Tile) {
-        self.tiles[y][x] = tile;
+    /// Coordinates of the `i`th tile of `line`, counting from the edge the
+    /// rocks roll towards in `direction`.
+    fn coords(&self, direction: Direction, line: usize, i: usize) -> (usize, usize) {
+        match direction {
+            Direction::Up => (line, i),
+            Direction::Down => (line, self.height - 1 - i),
+            Direction::Left => (i, line),
+            Direction::Right => (self.width - 1 - i, line),
+        }
     }
 
-    fn load(&self, direction: Direction) -> i32 {
+    fn line_dims(&self, direction: Direction) -> (usize, usize) {
         match direction {
-            Direction::Up => self.tiles.iter().enumerate().fold(0, |acc, (y, row)| {
-                let multiplier = self.tiles.len() - y;
-                acc + row.iter().filter(|tile| **tile == Tile::Round).count() as i32
-                    * multiplier as i32
-            }),
-            Direction::Down => todo!(),
-            Direction::Left => todo!(),
-            Direction::Right => todo!(),
+            Direction::Up | Direction::Down => (self.width, self.height),
+            Direction::Left | Direction::Right => (self.height, self.width),
+        }
+    }
+
+    /// Total load on the edge in `direction`.
+    fn load(&self, direction: Direction) -> usize {
+        let (lines, len) = self.line_dims(direction);
+        let mut load = 0;
+        for line in 0..lines {
+            for i in 0..len {
+                let (x, y) = self.coords(direction, line, i);
+                if self.rounds.get(x, y) {
+                    load += len - i;
+                }
+            }
         }
+        load
     }
+
     #[allow(dead_code)]
     fn print(&self) {
-        for row in &self.tiles {
-            for tile in row {
-                match tile {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                match self.get(x, y) {
                     Tile::Empty => eprint!("."),
                     Tile::Square => eprint!("#"),
                     Tile::Round => eprint!("O"),
@@ -75,85 +146,19 @@ impl Map {
     }
 
     fn roll(&mut self, direction: Direction) {
-        match direction {
-            Direction::Up => {
-                for x in 0..self.tiles[0].len() {
-                    let rounds = self
-                        .tiles
-                        .iter()
-                        .enumerate()
-                        .filter(|(_, row)| row[x] == Tile::Round)
-                        .map(|(y, _)| y)
-                        .collect::<Vec<_>>();
-                    for y in rounds {
-                        let mut y = y;
-                        while y > 0 && self.get(x, y - 1).unwrap() == &Tile::Empty && y > 0 {
-                            self.set(x, y, Tile::Empty);
-                            self.set(x, y - 1, Tile::Round);
-                            y -= 1;
-                        }
-                    }
-                }
-            }
-            Direction::Down => {
-                for x in 0..self.tiles[0].len() {
-                    let rounds = self
-                        .tiles
-                        .iter()
-                        .enumerate()
-                        .filter(|(_, row)| row[x] == Tile::Round)
-                        .map(|(y, _)| y)
-                        .rev()
-                        .collect::<Vec<_>>();
-                    for y in rounds {
-                        let mut y = y;
-                        while y < self.tiles.len() - 1
-                            && self.get(x, y + 1).unwrap() == &Tile::Empty
-                        {
-                            self.set(x, y, Tile::Empty);
-                            self.set(x, y + 1, Tile::Round);
-                            y += 1;
-                        }
-                    }
-                }
-            }
-            Direction::Left => {
-                for y in 0..self.tiles.len() {
-                    let rounds = self.tiles[y]
-                        .iter()
-                        .enumerate()
-                        .filter(|(_, tile)| **tile == Tile::Round)
-                        .map(|(x, _)| x)
-                        .collect::<Vec<_>>();
-                    for x in rounds {
-                        let mut x = x;
-                        while x > 0 && self.get(x - 1, y).unwrap() == &Tile::Empty {
-                            self.set(x, y, Tile::Empty);
-                            self.set(x - 1, y, Tile::Round);
-                            x -= 1;
-                        }
-                    }
-                }
-            }
-            Direction::Right => {
-                for y in 0..self.tiles.len() {
-                    let rounds = self.tiles[y]
-                        .iter()
-                        .enumerate()
-                        .filter(|(_, tile)| **tile == Tile::Round)
-                        .map(|(x, _)| x)
-                        .rev()
-                        .collect::<Vec<_>>();
-                    for x in rounds {
-                        let mut x = x;
-                        while x < self.tiles[0].len() - 1
-                            && self.get(x + 1, y).unwrap() == &Tile::Empty
-                        {
-                            self.set(x, y, Tile::Empty);
-                            self.set(x + 1, y, Tile::Round);
-                            x += 1;
-                        }
-                    }
+        let (lines, len) = self.line_dims(direction);
+        for line in 0..lines {
+            // First tile a rolling rock can stop on
+            let mut free = 0;
+            for i in 0..len {
+                let (x, y) = self.coords(direction, line, i);
+                if self.squares.get(x, y) {
+                    free = i + 1;
+                } else if self.rounds.get(x, y) {
+                    let (free_x, free_y) = self.coords(direction, line, free);
+                    self.rounds.set(x, y, false);
+                    self.rounds.set(free_x, free_y, true);
+                    free += 1;
                 }
             }
         }
@@ -173,41 +178,27 @@ impl Map {
     }
 }
 
-pub fn solve_task_one(#[allow(unused_variables)] input: Vec<String>) -> Result<i32> {
+pub fn solve_task_one(input: Vec<String>) -> Result<Answer> {
     let start_time = Instant::now();
     let mut map = Map::from_lines(input);
-    // map.print();
     map.roll(Direction::Up);
     let sol = map.load(Direction::Up);
     eprintln!("{:?}", Instant::now() - start_time);
-    Ok(sol)
+    Ok(sol.into())
 }
 
-pub fn solve_task_two(#[allow(unused_variables)] input: Vec<String>) -> Result<i32> {
+pub fn solve_task_two(input: Vec<String>) -> Result<Answer> {
     let start_time = Instant::now();
     let map = Map::from_lines(input);
 
-    // map.print();
-    let mut map = map;
-    let mut prev = vec![];
-    let mut cycle_start = None;
-    for _ in 0..1_000_000_000 {
-        prev.push(map.clone());
+    let cycle = find_cycle_from(map, |map| {
+        let mut map = map.clone();
         map.cycle();
-        if prev.contains(&map) {
-            cycle_start = Some(prev.iter().position(|m| *m == map).unwrap());
-            break;
-        }
-    }
-
-    let cycle_start = cycle_start.unwrap();
-    let cycle_length = prev.len() - cycle_start;
-    let cycle_index = (1_000_000_000 - cycle_start) % cycle_length;
-
-    let map = prev[cycle_start..][cycle_index].clone();
-    let sol = map.load(Direction::Up) as i32;
+        map
+    });
+    let sol = cycle.state_at(1_000_000_000).load(Direction::Up);
     eprintln!("{:?}", Instant::now() - start_time);
-    Ok(sol)
+    Ok(sol.into())
 }
 
 #[cfg(test)]
@@ -227,7 +218,7 @@ mod test {
         Ok(lines?)
     }
 
-    use crate::{solve_task_one, solve_task_two};
+    use crate::{solve_task_one, solve_task_two, Direction, Map};
 
     #[test]
     fn test_case_one_example() -> Result<()> {
@@ -262,4 +253,31 @@ mod test {
         assert_eq!(solve_task_two(file)?, 90982);
         Ok(())
     }
+
+    #[test]
+    fn test_roll_all_directions() {
+        let mut map = Map::from_lines(vec!["O.#.O".to_string(), ".O..O".to_string()]);
+        map.roll(Direction::Right);
+        assert_eq!(
+            map,
+            Map::from_lines(vec![".O#.O".to_string(), "...OO".to_string()])
+        );
+        map.roll(Direction::Left);
+        assert_eq!(
+            map,
+            Map::from_lines(vec!["O.#O.".to_string(), "OO...".to_string()])
+        );
+        map.roll(Direction::Down);
+        assert_eq!(
+            map,
+            Map::from_lines(vec!["O.#..".to_string(), "OO.O.".to_string()])
+        );
+        map.roll(Direction::Up);
+        assert_eq!(
+            map,
+            Map::from_lines(vec!["OO#O.".to_string(), "O....".to_string()])
+        );
+        assert_eq!(map.load(Direction::Up), 7);
+        assert_eq!(map.load(Direction::Left), 16);
+    }
 }
@@ -1,13 +1,24 @@
-use std::path::PathBuf;
-
+use clap::Parser;
 use color_eyre::Result;
 
-use common::get_file;
+use common::CommonCli;
+
+#[derive(Parser)]
+struct Args {
+    #[command(flatten)]
+    input: CommonCli,
+    /// Print the boxes after every step
+    #[arg(short, long)]
+    trace: bool,
+}
 
 fn main() -> Result<()> {
     color_eyre::install()?;
-    let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-    let file = get_file(cargo_manifest_dir.join("inputs/full.txt"))?;
+    let args = Args::parse();
+    let file = args.input.read_input(env!("CARGO_MANIFEST_DIR"))?;
+    if args.trace {
+        println!("{}", solution::trace(&file)?);
+    }
     let sol = solution::solve_task_two(file)?;
     println!("{sol}");
     Ok(())
@@ -1,6 +1,10 @@
-use std::time::Instant;
+use std::{fmt::Display, str::FromStr, time::Instant};
 
-use color_eyre::Result;
+use color_eyre::{eyre::anyhow, Result};
+use common::{
+    answer::{Answer, Checked, CheckedIterator},
+    bucket_map::BucketMap,
+};
 
 fn hash(input: &str) -> u32 {
     input
@@ -8,103 +12,168 @@ fn hash(input: &str) -> u32 {
         .fold(0, |acc, c| ((acc + c as u32) * 17) % 256)
 }
 
-pub fn solve_task_one(#[allow(unused_variables)] input: Vec<String>) -> Result<i32> {
+/// The initialization sequence, newlines are ignored
+fn steps(input: &[String]) -> impl Iterator<Item = &str> {
+    input
+        .iter()
+        .flat_map(|line| line.trim().split(','))
+        .filter(|step| !step.is_empty())
+}
+
+pub fn solve_task_one(input: Vec<String>) -> Result<Answer> {
     let start_time = Instant::now();
-    let sol = input[0].split(",").map(hash).sum::<u32>() as i32;
+    let sol = steps(&input).map(|step| hash(step) as u64).try_sum()?;
     eprintln!("{:?}", Instant::now() - start_time);
-    Ok(sol)
+    Ok(sol.into())
 }
-#[derive(Debug, Clone)]
-enum LensOperation {
-    Add(Lens),
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LensOperation {
+    Add { label: String, focal_length: u32 },
     Remove(String),
 }
 
 impl LensOperation {
-    fn from_str(input: &str) -> Self {
-        let split = &mut input.split(&['=', '-']);
-        let label = split.next().unwrap().to_string();
+    pub fn label(&self) -> &str {
+        match self {
+            Self::Add { label, .. } | Self::Remove(label) => label,
+        }
+    }
+}
 
-        if input.contains('=') {
-            let focal_length = split.next().unwrap().parse::<u32>().unwrap();
-            Self::Add(Lens {
-                label,
-                focal_length,
-            })
+impl FromStr for LensOperation {
+    type Err = color_eyre::Report;
+
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        let (label, operation) = if let Some(label) = input.strip_suffix('-') {
+            (label, Self::Remove(label.to_string()))
         } else {
-            Self::Remove(label)
+            let (label, focal_length) = input
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Step {input} is neither an = nor a - operation"))?;
+            let focal_length = match focal_length.parse() {
+                Ok(focal_length @ 1..=9) => focal_length,
+                _ => return Err(anyhow!("Invalid focal length {focal_length} in {input}")),
+            };
+            (
+                label,
+                Self::Add {
+                    label: label.to_string(),
+                    focal_length,
+                },
+            )
+        };
+        if label.is_empty() || !label.chars().all(|c| c.is_ascii_lowercase()) {
+            return Err(anyhow!("Invalid label {label:?} in {input}"));
         }
+        Ok(operation)
     }
 }
 
-#[derive(Debug, Clone)]
-struct Lens {
-    label: String,
-    pub focal_length: u32,
+impl Display for LensOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Add {
+                label,
+                focal_length,
+            } => write!(f, "{label}={focal_length}"),
+            Self::Remove(label) => write!(f, "{label}-"),
+        }
+    }
 }
+
+/// Focal lengths by label in the box given by the HASH of the label
 #[derive(Debug, Clone)]
-struct LensSystem {
-    lenses: [Vec<Lens>; 256],
+pub struct LensSystem {
+    lenses: BucketMap<String, u32>,
 }
 
-const EMPTY_VEC: Vec<Lens> = vec![];
-
 impl Default for LensSystem {
     fn default() -> Self {
         Self {
-            lenses: [EMPTY_VEC; 256],
+            lenses: BucketMap::new(256, |label| hash(label) as usize),
         }
     }
 }
 
 impl LensSystem {
-    fn add_lens(&mut self, lens: Lens) {
-        let idx = hash(&lens.label) as usize;
-        let add_idx = self.lenses[idx].iter().position(|l| l.label == lens.label);
-        if let Some(add) = add_idx {
-            self.lenses[idx][add].focal_length = lens.focal_length;
-        } else {
-            self.lenses[idx].push(lens);
+    pub fn perform_operation(&mut self, operation: &LensOperation) {
+        match operation {
+            LensOperation::Add {
+                label,
+                focal_length,
+            } => {
+                self.lenses.insert(label.clone(), *focal_length);
+            }
+            LensOperation::Remove(label) => {
+                self.lenses.remove(label);
+            }
         }
     }
 
-    fn remove_lens(&mut self, label: String) {
-        let idx = hash(&label) as usize;
-        let rm_idx = self.lenses[idx].iter().position(|l| l.label == label);
-        if let Some(rm) = rm_idx {
-            self.lenses[idx].remove(rm);
-        }
+    pub fn get_focal_length(&self) -> Result<u64> {
+        let powers = self
+            .lenses
+            .buckets()
+            .enumerate()
+            .flat_map(|(idx, lenses)| {
+                lenses
+                    .iter()
+                    .enumerate()
+                    .map(move |(lens_idx, (_, focal_length))| {
+                        (idx as u64 + 1)
+                            .try_mul(lens_idx as u64 + 1)?
+                            .try_mul(*focal_length as u64)
+                    })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        powers.into_iter().try_sum()
     }
+}
 
-    fn perform_operation(&mut self, operation: LensOperation) {
-        match operation {
-            LensOperation::Add(lens) => self.add_lens(lens),
-            LensOperation::Remove(label) => self.remove_lens(label),
+/// The boxes holding lenses, one per line like `Box 3: [ot 7] [ab 5]`
+impl Display for LensSystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (idx, lenses) in self.lenses.buckets().enumerate() {
+            if lenses.is_empty() {
+                continue;
+            }
+            write!(f, "Box {idx}:")?;
+            for (label, focal_length) in lenses {
+                write!(f, " [{label} {focal_length}]")?;
+            }
+            writeln!(f)?;
         }
+        Ok(())
     }
+}
 
-    fn get_focal_length(&self) -> i32 {
-        self.lenses.iter().enumerate().fold(0, |acc, (idx, lens)| {
-            acc + lens
-                .iter()
-                .enumerate()
-                .fold(0, |lens_acc, (lens_idx, lens)| {
-                    lens_acc + (idx as i32 + 1) * (lens_idx as i32 + 1) * lens.focal_length as i32
-                })
+pub fn parse_operations(input: &[String]) -> Result<Vec<LensOperation>> {
+    steps(input).map(str::parse).collect()
+}
+
+/// The boxes after every step, in the format of the puzzle's walkthrough
+pub fn trace(input: &[String]) -> Result<String> {
+    let mut lens_system = LensSystem::default();
+    let steps: Vec<String> = parse_operations(input)?
+        .iter()
+        .map(|operation| {
+            lens_system.perform_operation(operation);
+            format!("After \"{operation}\":\n{lens_system}")
         })
-    }
+        .collect();
+    Ok(steps.join("\n"))
 }
 
-pub fn solve_task_two(#[allow(unused_variables)] input: Vec<String>) -> Result<i32> {
+pub fn solve_task_two(input: Vec<String>) -> Result<Answer> {
     let start_time = Instant::now();
-    let operations: Vec<_> = input[0].split(",").map(LensOperation::from_str).collect();
     let mut lens_system = LensSystem::default();
-    for operation in operations {
-        lens_system.perform_operation(operation);
+    for operation in parse_operations(&input)? {
+        lens_system.perform_operation(&operation);
     }
-    let sol = lens_system.get_focal_length();
+    let sol = lens_system.get_focal_length()?;
     eprintln!("{:?}", Instant::now() - start_time);
-    Ok(sol)
+    Ok(sol.into())
 }
 
 #[cfg(test)]
@@ -124,7 +193,7 @@ mod test {
         Ok(lines?)
     }
 
-    use crate::{solve_task_one, solve_task_two};
+    use crate::{solve_task_one, solve_task_two, trace, LensOperation};
 
     #[test]
     fn test_case_one_example() -> Result<()> {
@@ -159,4 +228,21 @@ mod test {
         assert_eq!(solve_task_two(file)?, 244342);
         Ok(())
     }
+
+    #[test]
+    fn test_trace() -> Result<()> {
+        let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let file = get_file(cargo_manifest_dir.join("inputs/example_1.txt"))?;
+        let trace = trace(&file)?;
+        assert!(trace.starts_with("After \"rn=1\":\nBox 0: [rn 1]\n\nAfter \"cm-\":\n"));
+        assert!(
+            trace.ends_with("After \"ot=7\":\nBox 0: [rn 1] [cm 2]\nBox 3: [ot 7] [ab 5] [pc 6]\n")
+        );
+
+        for invalid in ["rn", "rn=", "rn=0", "rn=10", "=1", "-", "Rn-", "rn=1-"] {
+            assert!(invalid.parse::<LensOperation>().is_err(), "{invalid}");
+        }
+        assert_eq!("cm-".parse::<LensOperation>()?.label(), "cm");
+        Ok(())
+    }
 }
@@ -1,10 +1,9 @@
-use std::{
-    collections::{HashSet, VecDeque},
-    time::Instant,
-};
+#[cfg(test)]
+use std::collections::{HashSet, VecDeque};
+use std::time::Instant;
 
 use color_eyre::Result;
-use rayon::prelude::*;
+use common::answer::Answer;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum Direction {
@@ -70,57 +69,65 @@ impl Map {
     fn from_input(input: Vec<String>) -> Self {
         let elements = input
             .into_iter()
-            .map(|line| {
-                line.chars()
-                    .map(|c| Element::from_char(c))
-                    .collect::<Vec<_>>()
-            })
+            .map(|line| line.chars().map(Element::from_char).collect::<Vec<_>>())
             .collect::<Vec<_>>();
         Self { elements }
     }
 
+    fn width(&self) -> usize {
+        self.elements.first().map_or(0, |row| row.len())
+    }
+
+    fn height(&self) -> usize {
+        self.elements.len()
+    }
+
     fn get_element(&self, (x, y): (usize, usize)) -> Option<Element> {
         self.elements.get(y).and_then(|row| row.get(x)).copied()
     }
 
+    /// The neighbouring position in `dir`, `None` when that leaves the grid.
+    fn step(&self, (x, y): (usize, usize), dir: Direction) -> Option<(usize, usize)> {
+        match dir {
+            Direction::Up => Some((x, y.checked_sub(1)?)),
+            Direction::Down => (y + 1 < self.height()).then_some((x, y + 1)),
+            Direction::Left => Some((x.checked_sub(1)?, y)),
+            Direction::Right => (x + 1 < self.width()).then_some((x + 1, y)),
+        }
+    }
+
+    /// Every way a beam can enter the grid from its edges.
+    fn edge_entries(&self) -> Vec<((usize, usize), Direction)> {
+        let (width, height) = (self.width(), self.height());
+        let mut entries = vec![];
+        for y in 0..height {
+            entries.push(((0, y), Direction::Right));
+            entries.push(((width - 1, y), Direction::Left));
+        }
+        for x in 0..width {
+            entries.push(((x, 0), Direction::Down));
+            entries.push(((x, height - 1), Direction::Up));
+        }
+        entries
+    }
+
+    /// Plain beam simulation of a single entry, the reference the engine is
+    /// checked against.
+    #[cfg(test)]
     fn get_visited_locations(&self, entry: ((usize, usize), Direction)) -> HashSet<(usize, usize)> {
         let mut queue = VecDeque::new();
         queue.push_back(entry);
         let mut visited = HashSet::new();
         visited.insert(entry);
 
-        while let Some(((x, y), beam_dir)) = queue.pop_front() {
-            let element = self.get_element((x, y)).unwrap();
-            let egress_dirs = element.get_egress_dirs(beam_dir);
-            for dir in egress_dirs {
-                let (x, y) = match dir {
-                    Direction::Up => {
-                        if y == 0 {
-                            continue;
-                        }
-                        (x, y - 1)
-                    }
-                    Direction::Down => {
-                        if y == self.elements.len() - 1 {
-                            continue;
-                        }
-                        (x, y + 1)
-                    }
-                    Direction::Left => {
-                        if x == 0 {
-                            continue;
-                        }
-                        (x - 1, y)
-                    }
-                    Direction::Right => {
-                        if x == self.elements[0].len() - 1 {
-                            continue;
-                        }
-                        (x + 1, y)
-                    }
+        while let Some((pos, beam_dir)) = queue.pop_front() {
+            let element = self.get_element(pos).unwrap();
+            for dir in element.get_egress_dirs(beam_dir) {
+                let Some(next) = self.step(pos, dir) else {
+                    continue;
                 };
-                if visited.insert(((x, y), dir)) {
-                    queue.push_back(((x, y), dir));
+                if visited.insert((next, dir)) {
+                    queue.push_back((next, dir));
                 }
             }
         }
@@ -129,36 +136,257 @@ impl Map {
     }
 }
 
-pub fn solve_task_one(#[allow(unused_variables)] input: Vec<String>) -> Result<i32> {
+/// A set of tiles packed into 64 bit words.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TileSet {
+    words: Vec<u64>,
+}
+
+impl TileSet {
+    fn new(tiles: usize) -> Self {
+        Self {
+            words: vec![0; tiles.div_ceil(64)],
+        }
+    }
+
+    fn insert(&mut self, tile: usize) {
+        self.words[tile / 64] |= 1 << (tile % 64);
+    }
+
+    fn union_with(&mut self, other: &TileSet) {
+        self.words
+            .iter_mut()
+            .zip(&other.words)
+            .for_each(|(a, b)| *a |= b);
+    }
+
+    fn len(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+}
+
+/// The tiles a beam crosses until it leaves the grid or gets split.
+struct Ray {
+    tiles: Vec<usize>,
+    /// Splitter the beam ends in, if it gets split
+    split_at: Option<usize>,
+}
+
+/// Answers "which tiles get energized from this entry" for any entry without
+/// re-simulating the beam.
+///
+/// Between two splits a beam moves deterministically, so the grid breaks up
+/// into rays. Every splitter, once hit from the side, emits the same two rays
+/// no matter where the beam came from, so it becomes a node of a graph whose
+/// edges lead to the splitters its rays end in. Collapsing the strongly
+/// connected components of that graph leaves a DAG, and the energized tiles
+/// of every component are collected bottom-up into a bitset once.
+struct BeamEngine<'a> {
+    map: &'a Map,
+    /// Splitter id of every tile index
+    splitter_ids: Vec<Option<usize>>,
+    /// Component of every splitter
+    components: Vec<usize>,
+    /// Every tile energized once the splitters of a component are hit
+    energized: Vec<TileSet>,
+}
+
+impl<'a> BeamEngine<'a> {
+    fn new(map: &'a Map) -> Self {
+        let width = map.width();
+        let tile_count = width * map.height();
+        let mut splitter_ids = vec![None; tile_count];
+        let mut splitters = vec![];
+        for (y, row) in map.elements.iter().enumerate() {
+            for (x, element) in row.iter().enumerate() {
+                if matches!(
+                    element,
+                    Element::HorizontalSplitter | Element::VerticalSplitter
+                ) {
+                    splitter_ids[y * width + x] = Some(splitters.len());
+                    splitters.push((x, y));
+                }
+            }
+        }
+        let mut engine = Self {
+            map,
+            splitter_ids,
+            components: vec![],
+            energized: vec![],
+        };
+
+        let mut tiles = vec![];
+        let mut successors = vec![];
+        for &(x, y) in &splitters {
+            let mut splitter_tiles = vec![y * width + x];
+            let mut next = vec![];
+            let dirs = match map.elements[y][x] {
+                Element::VerticalSplitter => [Direction::Up, Direction::Down],
+                _ => [Direction::Left, Direction::Right],
+            };
+            for dir in dirs {
+                if let Some(pos) = map.step((x, y), dir) {
+                    let ray = engine.trace((pos, dir));
+                    splitter_tiles.extend(ray.tiles);
+                    next.extend(ray.split_at);
+                }
+            }
+            tiles.push(splitter_tiles);
+            successors.push(next);
+        }
+
+        engine.components = strongly_connected_components(&successors);
+        let component_count = engine.components.iter().max().map_or(0, |c| c + 1);
+        let mut members = vec![vec![]; component_count];
+        for (splitter, &component) in engine.components.iter().enumerate() {
+            members[component].push(splitter);
+        }
+        // Components are numbered so that every edge leads to the same or a
+        // lower component
+        for members in members {
+            let mut set = TileSet::new(tile_count);
+            for &splitter in &members {
+                tiles[splitter].iter().for_each(|&tile| set.insert(tile));
+                for &next in &successors[splitter] {
+                    let component = engine.components[next];
+                    if component < engine.energized.len() {
+                        set.union_with(&engine.energized[component]);
+                    }
+                }
+            }
+            engine.energized.push(set);
+        }
+        engine
+    }
+
+    /// Follows a beam standing on `start` until it leaves the grid or hits a
+    /// splitter from the side. Without splits every state has exactly one
+    /// predecessor, so the only state that can repeat is `start` itself.
+    fn trace(&self, start: ((usize, usize), Direction)) -> Ray {
+        let width = self.map.width();
+        let mut tiles = vec![];
+        let (mut pos, mut dir) = start;
+        loop {
+            let tile = pos.1 * width + pos.0;
+            tiles.push(tile);
+            let egress = self.map.get_element(pos).unwrap().get_egress_dirs(dir);
+            if egress.len() > 1 {
+                return Ray {
+                    tiles,
+                    split_at: self.splitter_ids[tile],
+                };
+            }
+            dir = egress[0];
+            match self.map.step(pos, dir) {
+                Some(next) if (next, dir) != start => pos = next,
+                _ => {
+                    return Ray {
+                        tiles,
+                        split_at: None,
+                    }
+                }
+            }
+        }
+    }
+
+    fn energized(&self, entry: ((usize, usize), Direction)) -> TileSet {
+        let ray = self.trace(entry);
+        let mut set = match ray.split_at {
+            Some(splitter) => self.energized[self.components[splitter]].clone(),
+            None => TileSet::new(self.splitter_ids.len()),
+        };
+        ray.tiles.iter().for_each(|&tile| set.insert(tile));
+        set
+    }
+}
+
+/// Tarjan's algorithm. Returns the component of every node, numbered in the
+/// order they are completed, so edges never lead to a higher component.
+fn strongly_connected_components(successors: &[Vec<usize>]) -> Vec<usize> {
+    struct State<'a> {
+        successors: &'a [Vec<usize>],
+        index: Vec<Option<usize>>,
+        low_link: Vec<usize>,
+        on_stack: Vec<bool>,
+        stack: Vec<usize>,
+        next_index: usize,
+        components: Vec<usize>,
+        next_component: usize,
+    }
+
+    fn visit(state: &mut State, node: usize) {
+        state.index[node] = Some(state.next_index);
+        state.low_link[node] = state.next_index;
+        state.next_index += 1;
+        state.stack.push(node);
+        state.on_stack[node] = true;
+
+        for &next in &state.successors[node] {
+            match state.index[next] {
+                None => {
+                    visit(state, next);
+                    state.low_link[node] = state.low_link[node].min(state.low_link[next]);
+                }
+                Some(index) if state.on_stack[next] => {
+                    state.low_link[node] = state.low_link[node].min(index);
+                }
+                Some(_) => {}
+            }
+        }
+
+        if Some(state.low_link[node]) == state.index[node] {
+            while let Some(member) = state.stack.pop() {
+                state.on_stack[member] = false;
+                state.components[member] = state.next_component;
+                if member == node {
+                    break;
+                }
+            }
+            state.next_component += 1;
+        }
+    }
+
+    let len = successors.len();
+    let mut state = State {
+        successors,
+        index: vec![None; len],
+        low_link: vec![0; len],
+        on_stack: vec![false; len],
+        stack: vec![],
+        next_index: 0,
+        components: vec![0; len],
+        next_component: 0,
+    };
+    for node in 0..len {
+        if state.index[node].is_none() {
+            visit(&mut state, node);
+        }
+    }
+    state.components
+}
+
+pub fn solve_task_one(input: Vec<String>) -> Result<Answer> {
     let start_time = Instant::now();
     let map = Map::from_input(input);
-    let visited_locations = &map.get_visited_locations(((0, 0), Direction::Right));
-    let sol = visited_locations.len();
+    let engine = BeamEngine::new(&map);
+    let sol = engine.energized(((0, 0), Direction::Right)).len();
     eprintln!("{:?}", Instant::now() - start_time);
-    Ok(sol as i32)
+    Ok(sol.into())
 }
 
-pub fn solve_task_two(#[allow(unused_variables)] input: Vec<String>) -> Result<i32> {
+pub fn solve_task_two(input: Vec<String>) -> Result<Answer> {
     let start_time = Instant::now();
     let map = Map::from_input(input);
-    let mut entry_locations = vec![];
-    for y in 0..map.elements.len() {
-        entry_locations.push(((0, y), Direction::Right));
-        entry_locations.push(((map.elements[y].len() - 1, y), Direction::Left));
-    }
-    for x in 0..map.elements[0].len() {
-        entry_locations.push(((x, 0), Direction::Down));
-        entry_locations.push(((x, map.elements.len() - 1), Direction::Up));
-    }
-    let visited_locations: Vec<usize> = entry_locations
-        .into_par_iter()
-        .map(|entry| map.get_visited_locations(entry).len())
-        .collect();
-
-    let sol = *visited_locations.iter().max().unwrap();
+    let engine = BeamEngine::new(&map);
+    let sol = map
+        .edge_entries()
+        .into_iter()
+        .map(|entry| engine.energized(entry).len())
+        .max()
+        .unwrap_or(0);
 
     eprintln!("{:?}", Instant::now() - start_time);
-    Ok(sol as i32)
+    Ok(sol.into())
 }
 
 #[cfg(test)]
@@ -178,7 +406,7 @@ mod test {
         Ok(lines?)
     }
 
-    use crate::{solve_task_one, solve_task_two};
+    use crate::{solve_task_one, solve_task_two, BeamEngine, Map};
 
     #[test]
     fn test_case_one_example() -> Result<()> {
@@ -213,4 +441,40 @@ mod test {
         assert_eq!(solve_task_two(file)?, 8163);
         Ok(())
     }
+
+    #[test]
+    fn test_engine_matches_simulation() -> Result<()> {
+        let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        for input in ["inputs/example_1.txt", "inputs/full.txt"] {
+            let map = Map::from_input(get_file(cargo_manifest_dir.join(input))?);
+            let engine = BeamEngine::new(&map);
+            for entry in map.edge_entries() {
+                assert_eq!(
+                    engine.energized(entry).len(),
+                    map.get_visited_locations(entry).len(),
+                    "{input} {entry:?}"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_engine_loops() {
+        // The beam circles between the splitters and mirrors forever
+        let map = Map::from_input(vec![
+            "./.\\.".to_string(),
+            "..|..".to_string(),
+            "..-..".to_string(),
+            ".\\./.".to_string(),
+        ]);
+        let engine = BeamEngine::new(&map);
+        for entry in map.edge_entries() {
+            assert_eq!(
+                engine.energized(entry).len(),
+                map.get_visited_locations(entry).len(),
+                "{entry:?}"
+            );
+        }
+    }
 }
@@ -3,7 +3,8 @@ use std::{
     time::Instant,
 };
 
-use color_eyre::Result;
+use color_eyre::{eyre::anyhow, Result};
+use common::answer::Answer;
 use rayon::prelude::*;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
@@ -21,23 +22,23 @@ struct QueueNode {
 
 impl PartialOrd for QueueNode {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.heat_loss.cmp(&other.heat_loss).reverse())
+        Some(self.cmp(other))
     }
 }
 
+/// Reversed so the `BinaryHeap` pops the lowest heat loss first
 impl Ord for QueueNode {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.heat_loss.cmp(&other.heat_loss)
+        self.heat_loss.cmp(&other.heat_loss).reverse()
     }
 }
 
-pub fn solve_task_one(#[allow(unused_variables)] input: Vec<String>) -> Result<i32> {
+pub fn solve_task_one(input: Vec<String>) -> Result<Answer> {
     let start_time = Instant::now();
     let input: Vec<Vec<u32>> = input
         .par_iter()
         .map(|x| {
             x.chars()
-                .into_iter()
                 .map(|x| x.to_digit(10).unwrap())
                 .collect::<Vec<u32>>()
         })
@@ -70,22 +71,21 @@ pub fn solve_task_one(#[allow(unused_variables)] input: Vec<String>) -> Result<i
             node.node.pos.1 + node.node.moving_dir.1,
         );
 
-        if node.node.moving_since < 3 && node.node.moving_dir != (0, 0) {
-            if 0 <= next_pos.0
-                && next_pos.0 < input[0].len() as i32
-                && 0 <= next_pos.1
-                && next_pos.1 < input.len() as i32
-            {
-                queue.push(QueueNode {
-                    heat_loss: node.heat_loss
-                        + input[next_pos.1 as usize][next_pos.0 as usize] as i32,
-                    node: Node {
-                        pos: next_pos,
-                        moving_dir: node.node.moving_dir,
-                        moving_since: node.node.moving_since + 1,
-                    },
-                });
-            }
+        if node.node.moving_since < 3
+            && node.node.moving_dir != (0, 0)
+            && 0 <= next_pos.0
+            && next_pos.0 < input[0].len() as i32
+            && 0 <= next_pos.1
+            && next_pos.1 < input.len() as i32
+        {
+            queue.push(QueueNode {
+                heat_loss: node.heat_loss + input[next_pos.1 as usize][next_pos.0 as usize] as i32,
+                node: Node {
+                    pos: next_pos,
+                    moving_dir: node.node.moving_dir,
+                    moving_since: node.node.moving_since + 1,
+                },
+            });
         }
         for (next_dir_row, next_dir_col) in [(0, 1), (1, 0), (0, -1), (-1, 0)] {
             if (next_dir_col, next_dir_row) != node.node.moving_dir
@@ -117,16 +117,15 @@ pub fn solve_task_one(#[allow(unused_variables)] input: Vec<String>) -> Result<i
     eprintln!("Iterations: {}", i);
 
     eprintln!("⏱️  Took: {:?}", Instant::now() - start_time);
-    Ok(sol.unwrap())
+    Ok(sol.ok_or(anyhow!("The goal can't be reached"))?.into())
 }
 
-pub fn solve_task_two(#[allow(unused_variables)] input: Vec<String>) -> Result<i32> {
+pub fn solve_task_two(input: Vec<String>) -> Result<Answer> {
     let start_time = Instant::now();
     let input: Vec<Vec<u32>> = input
         .par_iter()
         .map(|x| {
             x.chars()
-                .into_iter()
                 .map(|x| x.to_digit(10).unwrap())
                 .collect::<Vec<u32>>()
         })
@@ -161,22 +160,21 @@ pub fn solve_task_two(#[allow(unused_variables)] input: Vec<String>) -> Result<i
             node.node.pos.1 + node.node.moving_dir.1,
         );
 
-        if node.node.moving_since < 10 && node.node.moving_dir != (0, 0) {
-            if 0 <= next_pos.0
-                && next_pos.0 < input[0].len() as i32
-                && 0 <= next_pos.1
-                && next_pos.1 < input.len() as i32
-            {
-                queue.push(QueueNode {
-                    heat_loss: node.heat_loss
-                        + input[next_pos.1 as usize][next_pos.0 as usize] as i32,
-                    node: Node {
-                        pos: next_pos,
-                        moving_dir: node.node.moving_dir,
-                        moving_since: node.node.moving_since + 1,
-                    },
-                });
-            }
+        if node.node.moving_since < 10
+            && node.node.moving_dir != (0, 0)
+            && 0 <= next_pos.0
+            && next_pos.0 < input[0].len() as i32
+            && 0 <= next_pos.1
+            && next_pos.1 < input.len() as i32
+        {
+            queue.push(QueueNode {
+                heat_loss: node.heat_loss + input[next_pos.1 as usize][next_pos.0 as usize] as i32,
+                node: Node {
+                    pos: next_pos,
+                    moving_dir: node.node.moving_dir,
+                    moving_since: node.node.moving_since + 1,
+                },
+            });
         }
 
         if node.node.moving_dir == (0, 0) || node.node.moving_since >= 4 {
@@ -211,7 +209,7 @@ pub fn solve_task_two(#[allow(unused_variables)] input: Vec<String>) -> Result<i
     eprintln!("Iterations: {}", i);
 
     eprintln!("⏱️  Took: {:?}", Instant::now() - start_time);
-    Ok(sol.unwrap())
+    Ok(sol.ok_or(anyhow!("The goal can't be reached"))?.into())
 }
 
 #[cfg(test)]
@@ -0,0 +1,35 @@
+use clap::{Parser, ValueEnum};
+use color_eyre::Result;
+
+use common::CommonCli;
+use solution::{decode_direct, decode_hex, render_ascii, render_svg};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Svg,
+    Ascii,
+}
+
+/// Draws the lagoon described by a day 18 dig plan
+#[derive(Parser)]
+struct Args {
+    #[command(flatten)]
+    input: CommonCli,
+    #[arg(long, value_enum, default_value_t = Format::Svg)]
+    format: Format,
+    /// Decode the instructions from the color codes like part two
+    #[arg(long)]
+    hex: bool,
+}
+
+fn main() -> Result<()> {
+    color_eyre::install()?;
+    let args = Args::parse();
+    let file = args.input.read_input(env!("CARGO_MANIFEST_DIR"))?;
+    let decode = if args.hex { decode_hex } else { decode_direct };
+    match args.format {
+        Format::Svg => print!("{}", render_svg(&file, decode)?),
+        Format::Ascii => print!("{}", render_ascii(&file, decode)?),
+    }
+    Ok(())
+}
@@ -1,38 +1,45 @@
-use std::{str::FromStr, time::Instant};
+use std::{fmt::Write, str::FromStr, time::Instant};
 
-use color_eyre::Result;
-use rayon::prelude::*;
+use color_eyre::{eyre::anyhow, Result};
+use common::{
+    answer::Answer,
+    polygon::{Point, Polygon},
+};
+use csscolorparser::Color;
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
-enum Direction {
+pub enum Direction {
     Up,
     Down,
     Left,
     Right,
 }
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
-struct DigInstruction {
-    direction: Direction,
-    distance: i64,
+pub struct DigInstruction {
+    pub direction: Direction,
+    pub distance: i64,
 }
 
 impl DigInstruction {
-    fn from_hex_str(s: &str) -> Self {
-        let mut s = s.chars();
-        let last = s.by_ref().clone().last().unwrap();
-        let direction = match last {
-            '0' => Direction::Right,
-            '1' => Direction::Down,
-            '2' => Direction::Left,
-            '3' => Direction::Up,
-            _ => unreachable!(),
+    /// Decodes the six hex digits of a color code, the first five are the
+    /// distance and the last one the direction.
+    pub fn from_hex_str(s: &str) -> Result<Self> {
+        if s.len() != 6 || !s.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(anyhow!("Invalid hex instruction: {s}"));
+        }
+        let direction = match &s[5..] {
+            "0" => Direction::Right,
+            "1" => Direction::Down,
+            "2" => Direction::Left,
+            "3" => Direction::Up,
+            d => return Err(anyhow!("Invalid hex direction: {d}")),
         };
-        let distance = i64::from_str_radix(&s.take(5).collect::<String>(), 16).unwrap();
+        let distance = i64::from_str_radix(&s[..5], 16)?;
 
-        Self {
+        Ok(Self {
             direction,
             distance,
-        }
+        })
     }
 }
 
@@ -40,15 +47,18 @@ impl FromStr for DigInstruction {
     type Err = color_eyre::Report;
 
     fn from_str(s: &str) -> std::prelude::v1::Result<Self, Self::Err> {
-        let mut s = s.split_whitespace();
-        let direction = match s.next().unwrap() {
-            "U" => Direction::Up,
-            "D" => Direction::Down,
-            "L" => Direction::Left,
-            "R" => Direction::Right,
-            _ => unreachable!(),
+        let mut parts = s.split_whitespace();
+        let direction = match parts.next() {
+            Some("U") => Direction::Up,
+            Some("D") => Direction::Down,
+            Some("L") => Direction::Left,
+            Some("R") => Direction::Right,
+            _ => return Err(anyhow!("Invalid direction in instruction: {s}")),
         };
-        let distance = s.next().unwrap().parse::<i64>()?;
+        let distance = parts
+            .next()
+            .ok_or(anyhow!("Missing distance in instruction: {s}"))?
+            .parse::<i64>()?;
 
         Ok(Self {
             direction,
@@ -57,90 +67,182 @@ impl FromStr for DigInstruction {
     }
 }
 
-pub fn solve_task_one(#[allow(unused_variables)] input: Vec<String>) -> Result<i64> {
-    let start_time = Instant::now();
+/// The hex digits between `(#` and `)` of a dig plan line
+fn hex_field(line: &str) -> Result<&str> {
+    let start = line
+        .find("(#")
+        .ok_or(anyhow!("Missing color in line: {line}"))?;
+    let end = line[start..]
+        .find(')')
+        .ok_or(anyhow!("Unclosed color in line: {line}"))?;
+    Ok(&line[start + 2..start + end])
+}
 
-    let instructions = input
-        .iter()
-        .map(|s| s.parse::<DigInstruction>())
-        .collect::<Result<Vec<DigInstruction>>>()?;
-
-    let (vertices, bounding_points) =
-        instructions
-            .iter()
-            .fold((vec![(0, 0)], 0), |(mut acc, b), i| {
-                let prev = acc.last().unwrap();
-
-                let next = match i.direction {
-                    Direction::Up => (prev.0, prev.1 + i.distance),
-                    Direction::Down => (prev.0, prev.1 - i.distance),
-                    Direction::Left => (prev.0 - i.distance, prev.1),
-                    Direction::Right => (prev.0 + i.distance, prev.1),
-                };
-
-                acc.push(next);
-                (acc, b + i.distance)
-            });
-    // let vertices: HashSet<(i64, i64)> = HashSet::from_iter(vertices.iter().cloned());
-    let area: i64 = vertices
-        .par_windows(3)
-        .map(|window| {
-            let ((_, y_m1), (x_n, _), (_, y_p1)) = (window[0], window[1], window[2]);
+/// Decodes a dig plan line the way part one reads it
+pub fn decode_direct(line: &str) -> Result<DigInstruction> {
+    line.parse()
+}
 
-            x_n * (y_p1 - y_m1)
-        })
-        .sum();
-    let area = area.abs() / 2;
+/// Decodes a dig plan line from its color code the way part two reads it
+pub fn decode_hex(line: &str) -> Result<DigInstruction> {
+    DigInstruction::from_hex_str(hex_field(line)?)
+}
 
-    let interior_points = area - bounding_points / 2 + 1;
+/// Corners of the trench in digging order, starting at the origin with y
+/// pointing up
+fn trench_vertices(instructions: &[DigInstruction]) -> Vec<Point> {
+    let mut vertices = vec![(0, 0)];
+    for i in instructions {
+        let (x, y) = *vertices.last().unwrap();
+        vertices.push(match i.direction {
+            Direction::Up => (x, y + i.distance),
+            Direction::Down => (x, y - i.distance),
+            Direction::Left => (x - i.distance, y),
+            Direction::Right => (x + i.distance, y),
+        });
+    }
+    vertices
+}
 
-    eprintln!("{:?}", Instant::now() - start_time);
-    Ok(interior_points + bounding_points)
+/// The polygon traced by the trench. A plan that doesn't end where it
+/// started is closed by a straight trench back to the start.
+pub fn dig_polygon(instructions: &[DigInstruction]) -> Polygon {
+    Polygon::new(trench_vertices(instructions))
 }
 
-pub fn solve_task_two(#[allow(unused_variables)] input: Vec<String>) -> Result<i64> {
-    let start_time = Instant::now();
+/// Decodes every line of a dig plan with `decode`
+pub fn decode_plan(
+    input: &[String],
+    decode: impl Fn(&str) -> Result<DigInstruction>,
+) -> Result<Vec<DigInstruction>> {
+    input.iter().map(|line| decode(line)).collect()
+}
 
-    let instructions = input
-        .iter()
-        .map(|s| {
-            let start = s.find("(#").unwrap();
-            let end = s.find(")").unwrap();
-            DigInstruction::from_hex_str(&s[start + 2..end])
-        })
-        .collect::<Vec<DigInstruction>>();
-
-    let (vertices, bounding_points) =
-        instructions
-            .iter()
-            .fold((vec![(0, 0)], 0), |(mut acc, b), i| {
-                let prev = acc.last().unwrap();
-
-                let next = match i.direction {
-                    Direction::Up => (prev.0, prev.1 + i.distance),
-                    Direction::Down => (prev.0, prev.1 - i.distance),
-                    Direction::Left => (prev.0 - i.distance, prev.1),
-                    Direction::Right => (prev.0 + i.distance, prev.1),
-                };
-
-                acc.push(next);
-                (acc, b + i.distance)
-            });
+/// Cubic meters of lava the lagoon dug by the plan holds. The trench is one
+/// cube wide, so that's every lattice point on or inside the polygon.
+pub fn lagoon_volume(
+    input: &[String],
+    decode: impl Fn(&str) -> Result<DigInstruction>,
+) -> Result<i64> {
+    dig_polygon(&decode_plan(input, decode)?).lattice_points()
+}
+
+fn bounds(vertices: &[Point]) -> (Point, Point) {
+    vertices.iter().fold(
+        ((i64::MAX, i64::MAX), (i64::MIN, i64::MIN)),
+        |((min_x, min_y), (max_x, max_y)), &(x, y)| {
+            ((min_x.min(x), min_y.min(y)), (max_x.max(x), max_y.max(y)))
+        },
+    )
+}
 
-    let area: i64 = vertices
-        .windows(3)
-        .map(|window| {
-            let ((_, y_m1), (x_n, _), (_, y_p1)) = (window[0], window[1], window[2]);
+/// Side length in pixels of the longer side of an exported SVG
+const SVG_SIZE: f64 = 800.0;
+
+/// Exports the plan decoded by `decode` as an SVG scaled to fit
+/// `SVG_SIZE`. Each trench segment is stroked with the color code of the
+/// line it was decoded from.
+pub fn render_svg(
+    input: &[String],
+    decode: impl Fn(&str) -> Result<DigInstruction>,
+) -> Result<String> {
+    let instructions = decode_plan(input, decode)?;
+    let colors = input
+        .iter()
+        .map(|line| Ok(csscolorparser::parse(&format!("#{}", hex_field(line)?))?))
+        .collect::<Result<Vec<Color>>>()?;
+    let vertices = trench_vertices(&instructions);
+    let ((min_x, min_y), (max_x, max_y)) = bounds(&vertices);
+    let extent = (max_x - min_x).max(max_y - min_y).max(1) as f64;
+    let scale = SVG_SIZE / extent;
+    let margin = 4.0;
+    // SVG y points down, so rows are counted from the top of the plan
+    let project = |(x, y): Point| {
+        (
+            margin + (x - min_x) as f64 * scale,
+            margin + (max_y - y) as f64 * scale,
+        )
+    };
 
-            x_n * (y_p1 - y_m1)
+    let (width, height) = project((max_x, min_y));
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\">\n",
+        width + margin,
+        height + margin
+    );
+    let points = vertices
+        .iter()
+        .map(|&v| {
+            let (x, y) = project(v);
+            format!("{x:.2},{y:.2}")
         })
-        .sum();
-    let area = area.abs() / 2;
+        .collect::<Vec<_>>()
+        .join(" ");
+    writeln!(
+        svg,
+        "  <polygon points=\"{points}\" fill=\"#dddddd\" stroke=\"none\"/>"
+    )?;
+    for (edge, color) in vertices.windows(2).zip(colors) {
+        let ((x1, y1), (x2, y2)) = (project(edge[0]), project(edge[1]));
+        writeln!(
+            svg,
+            "  <line x1=\"{x1:.2}\" y1=\"{y1:.2}\" x2=\"{x2:.2}\" y2=\"{y2:.2}\" stroke=\"{}\" stroke-width=\"2\" stroke-linecap=\"square\"/>",
+            color.to_hex_string()
+        )?;
+    }
+    svg.push_str("</svg>\n");
+    Ok(svg)
+}
+
+/// Plans wider or taller than this many cubes are refused by `render_ascii`
+const MAX_ASCII_SIZE: i64 = 200;
+
+/// Draws the plan decoded by `decode` as text: `#` for the trench, `~` for
+/// the dug out interior and `.` for untouched ground.
+pub fn render_ascii(
+    input: &[String],
+    decode: impl Fn(&str) -> Result<DigInstruction>,
+) -> Result<String> {
+    let instructions = decode_plan(input, decode)?;
+    let vertices = trench_vertices(&instructions);
+    let ((min_x, min_y), (max_x, max_y)) = bounds(&vertices);
+    if max_x - min_x >= MAX_ASCII_SIZE || max_y - min_y >= MAX_ASCII_SIZE {
+        return Err(anyhow!(
+            "Plan spans {}x{} cubes, too large to draw",
+            max_x - min_x + 1,
+            max_y - min_y + 1
+        ));
+    }
+    let polygon = Polygon::new(vertices);
+
+    let mut out = String::new();
+    for y in (min_y..=max_y).rev() {
+        for x in min_x..=max_x {
+            out.push(if polygon.on_boundary((x, y)) {
+                '#'
+            } else if polygon.contains((x, y)) {
+                '~'
+            } else {
+                '.'
+            });
+        }
+        out.push('\n');
+    }
+    Ok(out)
+}
 
-    let interior_points = area - bounding_points / 2 + 1;
+pub fn solve_task_one(input: Vec<String>) -> Result<Answer> {
+    let start_time = Instant::now();
+    let sol = lagoon_volume(&input, decode_direct)?;
+    eprintln!("{:?}", Instant::now() - start_time);
+    Ok(sol.into())
+}
 
+pub fn solve_task_two(input: Vec<String>) -> Result<Answer> {
+    let start_time = Instant::now();
+    let sol = lagoon_volume(&input, decode_hex)?;
     eprintln!("{:?}", Instant::now() - start_time);
-    Ok(interior_points + bounding_points)
+    Ok(sol.into())
 }
 
 #[cfg(test)]
@@ -160,7 +262,10 @@ mod test {
         Ok(lines?)
     }
 
-    use crate::{solve_task_one, solve_task_two};
+    use crate::{
+        decode_direct, decode_hex, decode_plan, dig_polygon, lagoon_volume, render_ascii,
+        render_svg, solve_task_one, solve_task_two, DigInstruction, Direction,
+    };
 
     #[test]
     fn test_case_one_example() -> Result<()> {
@@ -195,4 +300,91 @@ mod test {
         assert_eq!(solve_task_two(file)?, 96556251590677);
         Ok(())
     }
+
+    #[test]
+    fn test_parse_instructions() -> Result<()> {
+        assert_eq!(
+            decode_direct("L 12 (#70c710)")?,
+            DigInstruction {
+                direction: Direction::Left,
+                distance: 12
+            }
+        );
+        assert_eq!(
+            decode_hex("R 6 (#70c710)")?,
+            DigInstruction {
+                direction: Direction::Right,
+                distance: 461937
+            }
+        );
+        assert_eq!(
+            DigInstruction::from_hex_str("0dc571")?,
+            DigInstruction {
+                direction: Direction::Down,
+                distance: 56407
+            }
+        );
+        assert!(decode_direct("X 5 (#70c710)").is_err());
+        assert!(decode_direct("R").is_err());
+        assert!(decode_direct("R five").is_err());
+        assert!(decode_hex("R 6").is_err());
+        assert!(DigInstruction::from_hex_str("70c71").is_err());
+        assert!(DigInstruction::from_hex_str("70c714").is_err());
+        assert!(DigInstruction::from_hex_str("+0c710").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_ascii() -> Result<()> {
+        let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let file = get_file(cargo_manifest_dir.join("inputs/example_1.txt"))?;
+        let drawing = render_ascii(&file, decode_direct)?;
+        assert_eq!(
+            drawing,
+            "#######\n#~~~~~#\n###~~~#\n..#~~~#\n..#~~~#\n###~###\n#~~~#..\n##~~###\n.#~~~~#\n.######\n"
+        );
+        assert_eq!(
+            solve_task_one(file.clone())?,
+            drawing.chars().filter(|&c| c == '#' || c == '~').count() as i64
+        );
+        assert!(render_ascii(&file, decode_hex).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_svg() -> Result<()> {
+        let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let file = get_file(cargo_manifest_dir.join("inputs/example_1.txt"))?;
+        for svg in [
+            render_svg(&file, decode_direct)?,
+            render_svg(&file, decode_hex)?,
+        ] {
+            assert!(svg.starts_with("<svg"));
+            assert_eq!(svg.matches("<line").count(), file.len());
+            assert!(svg.contains("stroke=\"#70c710\""));
+            assert!(svg.contains("stroke=\"#7a21e3\""));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_unclosed_plans() -> Result<()> {
+        let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let file = get_file(cargo_manifest_dir.join("inputs/example_1.txt"))?;
+        // The last instruction leads straight back to the start
+        let unclosed = &file[..file.len() - 1];
+        assert_eq!(lagoon_volume(unclosed, decode_direct)?, 62);
+        assert_eq!(
+            dig_polygon(&decode_plan(unclosed, decode_direct)?),
+            dig_polygon(&decode_plan(&file, decode_direct)?)
+        );
+
+        // Closing the L shape needs a diagonal back to the start
+        let plan = ["R 2", "D 2"].map(String::from);
+        assert_eq!(lagoon_volume(&plan, decode_direct)?, 6);
+        // Any decoder works, not only the two used by the tasks
+        let plan = ["R 4", "U 3", "L 4"].map(String::from);
+        assert_eq!(lagoon_volume(&plan, |line| line.parse())?, 20);
+        Ok(())
+    }
 }
@@ -0,0 +1,27 @@
+use std::path::PathBuf;
+
+use common::get_file;
+use criterion::{criterion_group, criterion_main, Criterion};
+use solution::parse_input;
+
+fn bench_workflows(c: &mut Criterion) {
+    let input = get_file(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("inputs/full.txt"))
+        .expect("the personal input exists");
+    c.bench_function("parse", |b| b.iter(|| parse_input(&input).unwrap()));
+
+    let (workflows, parts) = parse_input(&input).unwrap();
+    c.bench_function("interpret", |b| {
+        b.iter(|| {
+            parts
+                .iter()
+                .filter(|part| workflows.run(part).unwrap())
+                .count()
+        })
+    });
+    c.bench_function("simplify and compile", |b| {
+        b.iter(|| workflows.simplify().compile().unwrap())
+    });
+}
+
+criterion_group!(benches, bench_workflows);
+criterion_main!(benches);
@@ -1,14 +1,28 @@
-use std::path::PathBuf;
-
+use clap::Parser;
 use color_eyre::Result;
 
-use common::get_file;
+use common::CommonCli;
+
+#[derive(Parser)]
+struct Args {
+    #[command(flatten)]
+    input: CommonCli,
+    /// Print the accepted regions of attribute values before the total
+    #[arg(short, long)]
+    regions: bool,
+}
 
 fn main() -> Result<()> {
     color_eyre::install()?;
-    let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-    let file = get_file(cargo_manifest_dir.join("inputs/full.txt"))?;
-    let sol = solution::solve_task_two(file)?;
-    println!("{sol}");
+    let args = Args::parse();
+    let file = args.input.read_input(env!("CARGO_MANIFEST_DIR"))?;
+    if args.regions {
+        let regions = solution::find_accepted_regions(&file, solution::DEFAULT_BOUNDS)?;
+        print!("{regions}");
+        println!("{}", regions.total()?);
+    } else {
+        let sol = solution::solve_task_two(file)?;
+        println!("{sol}");
+    }
     Ok(())
 }
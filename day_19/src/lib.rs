@@ -1,154 +1,173 @@
 use std::{
-    collections::{HashMap, VecDeque},
-    ops::Range,
-    str::FromStr,
+    collections::{HashSet, VecDeque},
+    fmt::Display,
+    ops::{Range, RangeInclusive},
     time::Instant,
 };
 
-use color_eyre::Result;
+use color_eyre::{eyre::anyhow, Result};
+use common::{
+    answer::{Answer, CheckedIterator},
+    graph::{Graph, GraphBuilder, Id},
+    interval::HyperRect,
+};
 
+/// Index of an attribute in its [`AttributeSet`]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum Attribute {
-    ExtremelyCoolLooking,
-    Musical,
-    Aerodynamic,
-    Shiny,
+pub struct Attribute(usize);
+
+/// Names of the part attributes, in the order their values are stored
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AttributeSet {
+    names: Vec<String>,
 }
 
-impl Attribute {
-    fn char_repr(&self) -> char {
-        match self {
-            Attribute::ExtremelyCoolLooking => 'x',
-            Attribute::Musical => 'm',
-            Attribute::Aerodynamic => 'a',
-            Attribute::Shiny => 's',
-        }
+impl AttributeSet {
+    pub fn names(&self) -> &[String] {
+        &self.names
     }
 
-    fn from_char(c: char) -> Self {
-        match c {
-            'x' => Self::ExtremelyCoolLooking,
-            'm' => Self::Musical,
-            'a' => Self::Aerodynamic,
-            's' => Self::Shiny,
-            _ => panic!("Invalid attribute"),
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn get(&self, name: &str) -> Option<Attribute> {
+        self.names.iter().position(|n| n == name).map(Attribute)
+    }
+
+    fn get_or_insert(&mut self, name: &str) -> Attribute {
+        self.get(name).unwrap_or_else(|| {
+            self.names.push(name.to_string());
+            Attribute(self.names.len() - 1)
+        })
+    }
+
+    /// Attributes named in a part like `{x=787,m=2655,a=1222,s=2876}`, in order
+    fn from_part(s: &str) -> Result<Self> {
+        let mut attributes = Self::default();
+        for rating in s.trim_matches(['{', '}']).split(',') {
+            let (name, _) = rating
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Invalid rating {rating}"))?;
+            attributes.get_or_insert(name.trim());
         }
+        Ok(attributes)
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Part {
-    x: usize,
-    m: usize,
-    a: usize,
-    s: usize,
+/// Attribute values in the order of the [`AttributeSet`] they were parsed with
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Part {
+    values: Vec<u64>,
 }
 
 impl Part {
-    fn total_rating(&self) -> usize {
-        self.x + self.m + self.a + self.s
+    fn get(&self, attribute: Attribute) -> u64 {
+        self.values[attribute.0]
     }
-}
 
-impl FromStr for Part {
-    type Err = color_eyre::Report;
+    fn total_rating(&self) -> Result<u64> {
+        self.values.iter().copied().try_sum()
+    }
 
-    fn from_str(s: &str) -> std::prelude::v1::Result<Self, Self::Err> {
-        let s = s.replace(&['{', '}'], "");
-        let mut split = s.split(',');
-        let x = split.next().unwrap().trim()[2..].parse()?;
-        let m = split.next().unwrap().trim()[2..].parse()?;
-        let a = split.next().unwrap().trim()[2..].parse()?;
-        let s = split.next().unwrap().trim()[2..].parse()?;
-        Ok(Self { x, m, a, s })
+    fn parse(s: &str, attributes: &AttributeSet) -> Result<Self> {
+        let mut values = vec![None; attributes.len()];
+        for rating in s.trim_matches(['{', '}']).split(',') {
+            let (name, value) = rating
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Invalid rating {rating}"))?;
+            let attribute = attributes
+                .get(name.trim())
+                .ok_or_else(|| anyhow!("Unknown attribute {name} in {s}"))?;
+            values[attribute.0] = Some(value.trim().parse()?);
+        }
+        let values = values
+            .into_iter()
+            .zip(attributes.names())
+            .map(|(value, name)| value.ok_or_else(|| anyhow!("Part {s} has no {name} rating")))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { values })
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum Condition {
-    GreaterThan(Attribute, usize),
-    LessThan(Attribute, usize),
+pub enum Condition {
+    GreaterThan(Attribute, u64),
+    LessThan(Attribute, u64),
 }
 
 impl Condition {
     fn apply_condition(&self, part: &Part) -> bool {
         match self {
-            Self::GreaterThan(attribute, value) => match attribute {
-                Attribute::ExtremelyCoolLooking => part.x > *value,
-                Attribute::Musical => part.m > *value,
-                Attribute::Aerodynamic => part.a > *value,
-                Attribute::Shiny => part.s > *value,
-            },
-            Self::LessThan(attribute, value) => match attribute {
-                Attribute::ExtremelyCoolLooking => part.x < *value,
-                Attribute::Musical => part.m < *value,
-                Attribute::Aerodynamic => part.a < *value,
-                Attribute::Shiny => part.s < *value,
-            },
+            Self::GreaterThan(attribute, value) => part.get(*attribute) > *value,
+            Self::LessThan(attribute, value) => part.get(*attribute) < *value,
         }
     }
-}
 
-impl FromStr for Condition {
-    type Err = color_eyre::Report;
+    /// Splits the constraint into the parts matching and not matching the condition
+    fn split_constraint(
+        &self,
+        constraint: &PartConstraint,
+    ) -> (Option<PartConstraint>, Option<PartConstraint>) {
+        match self {
+            Self::GreaterThan(attribute, value) => {
+                let (below, above) = constraint.ranges.split_at(attribute.0, value + 1);
+                (
+                    above.map(|ranges| PartConstraint { ranges }),
+                    below.map(|ranges| PartConstraint { ranges }),
+                )
+            }
+            Self::LessThan(attribute, value) => {
+                let (below, above) = constraint.ranges.split_at(attribute.0, *value);
+                (
+                    below.map(|ranges| PartConstraint { ranges }),
+                    above.map(|ranges| PartConstraint { ranges }),
+                )
+            }
+        }
+    }
 
-    fn from_str(s: &str) -> std::prelude::v1::Result<Self, Self::Err> {
-        if s.contains('<') {
-            let mut split = s.split('<');
-            let attribute =
-                Attribute::from_char(split.next().unwrap().trim().chars().last().unwrap());
-            let value = split.next().unwrap().trim().parse()?;
-            Ok(Self::LessThan(attribute, value))
-        } else if s.contains('>') {
-            let mut split = s.split('>');
-            let attribute =
-                Attribute::from_char(split.next().unwrap().trim().chars().last().unwrap());
-            let value = split.next().unwrap().trim().parse()?;
+    fn parse(s: &str, attributes: &mut AttributeSet) -> Result<Self> {
+        if let Some((name, value)) = s.split_once('<') {
+            let attribute = attributes.get_or_insert(name.trim());
+            Ok(Self::LessThan(attribute, value.trim().parse()?))
+        } else if let Some((name, value)) = s.split_once('>') {
+            let attribute = attributes.get_or_insert(name.trim());
+            let value: u64 = value.trim().parse()?;
+            // Parts above the value start at the next one
+            value
+                .checked_add(1)
+                .ok_or_else(|| anyhow!("Condition {s} can't be satisfied"))?;
             Ok(Self::GreaterThan(attribute, value))
         } else {
-            panic!("Invalid condition")
+            Err(anyhow!("Invalid condition {s}"))
         }
     }
 }
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-enum NextStep {
-    Workflow(String),
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NextStep {
+    Workflow(Id),
     Accepted,
     Rejected,
 }
 
 impl NextStep {
-    fn apply_next_step(&self, part: Part, workflows: &HashMap<String, Workflow>) -> Self {
-        match self {
-            Self::Workflow(s) => {
-                let workflow = s.clone();
-                let workflow = workflows.get(&workflow).unwrap();
-                for rule in &workflow.rules {
-                    if let Some(next_step) = rule.apply_rule(&part) {
-                        return next_step;
-                    }
-                }
-                workflow.default_next_step.clone()
-            }
-            Self::Accepted => Self::Accepted,
-            Self::Rejected => Self::Rejected,
-        }
-    }
-}
-
-impl FromStr for NextStep {
-    type Err = color_eyre::Report;
-
-    fn from_str(s: &str) -> std::prelude::v1::Result<Self, Self::Err> {
+    fn parse(s: &str, names: &mut GraphBuilder<Option<Workflow>>) -> Self {
         match s {
-            "A" => Ok(Self::Accepted),
-            "R" => Ok(Self::Rejected),
-            _ => Ok(Self::Workflow(s.to_string())),
+            "A" => Self::Accepted,
+            "R" => Self::Rejected,
+            _ => Self::Workflow(names.id(s)),
         }
     }
 }
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-struct Rule {
+pub struct Rule {
     condition: Condition,
     next_step: NextStep,
 }
@@ -156,7 +175,7 @@ struct Rule {
 impl Rule {
     fn apply_rule(&self, part: &Part) -> Option<NextStep> {
         if self.condition.apply_condition(part) {
-            Some(self.next_step.clone())
+            Some(self.next_step)
         } else {
             None
         }
@@ -165,105 +184,23 @@ impl Rule {
     fn apply_rule_part_constraint(
         &self,
         part_constraint: &PartConstraint,
-    ) -> (Option<(PartConstraint, NextStep)>, PartConstraint) {
-        match &self.condition {
-            Condition::GreaterThan(attr, val) => {
-                let val = *val as i64;
-                let mut new_constraint = part_constraint.clone();
-                let mut old_constraint = part_constraint.clone();
-                match attr {
-                    Attribute::ExtremelyCoolLooking => {
-                        if new_constraint.x.contains(&val) {
-                            new_constraint.x.start = val + 1;
-                            old_constraint.x.end = val + 1;
-                        } else {
-                            return (None, old_constraint);
-                        }
-                    }
-                    Attribute::Musical => {
-                        if new_constraint.m.contains(&val) {
-                            new_constraint.m.start = val + 1;
-                            old_constraint.m.end = val + 1;
-                        } else {
-                            return (None, old_constraint);
-                        }
-                    }
-                    Attribute::Aerodynamic => {
-                        if new_constraint.a.contains(&val) {
-                            new_constraint.a.start = val + 1;
-                            old_constraint.a.end = val + 1;
-                        } else {
-                            return (None, old_constraint);
-                        }
-                    }
-                    Attribute::Shiny => {
-                        if new_constraint.s.contains(&val) {
-                            new_constraint.s.start = val + 1;
-                            old_constraint.s.end = val + 1;
-                        } else {
-                            return (None, old_constraint);
-                        }
-                    }
-                }
-                (
-                    Some((new_constraint, self.next_step.clone())),
-                    old_constraint,
-                )
-            }
-            Condition::LessThan(attr, val) => {
-                let val = *val as i64;
-                let mut new_constraint = part_constraint.clone();
-                let mut old_constraint = part_constraint.clone();
-                match attr {
-                    Attribute::ExtremelyCoolLooking => {
-                        if new_constraint.x.contains(&val) {
-                            new_constraint.x.end = val;
-                            old_constraint.x.start = val;
-                        } else {
-                            return (None, old_constraint);
-                        }
-                    }
-                    Attribute::Musical => {
-                        if new_constraint.m.contains(&val) {
-                            new_constraint.m.end = val;
-                            old_constraint.m.start = val;
-                        } else {
-                            return (None, old_constraint);
-                        }
-                    }
-                    Attribute::Aerodynamic => {
-                        if new_constraint.a.contains(&val) {
-                            new_constraint.a.end = val;
-                            old_constraint.a.start = val;
-                        } else {
-                            return (None, old_constraint);
-                        }
-                    }
-                    Attribute::Shiny => {
-                        if new_constraint.s.contains(&val) {
-                            new_constraint.s.end = val;
-                            old_constraint.s.start = val;
-                        } else {
-                            return (None, old_constraint);
-                        }
-                    }
-                }
-                (
-                    Some((new_constraint, self.next_step.clone())),
-                    old_constraint,
-                )
-            }
-        }
+    ) -> (Option<(PartConstraint, NextStep)>, Option<PartConstraint>) {
+        let (matching, remaining) = self.condition.split_constraint(part_constraint);
+        (matching.map(|c| (c, self.next_step)), remaining)
     }
 }
 
-impl FromStr for Rule {
-    type Err = color_eyre::Report;
-
-    fn from_str(s: &str) -> std::prelude::v1::Result<Self, Self::Err> {
-        let mut split = s.split(':');
-        let condition = split.next().unwrap().trim().parse()?;
-        let next_step = split.next().unwrap().trim().parse()?;
+impl Rule {
+    fn parse(
+        s: &str,
+        attributes: &mut AttributeSet,
+        names: &mut GraphBuilder<Option<Workflow>>,
+    ) -> Result<Self> {
+        let (condition, next_step) = s
+            .split_once(':')
+            .ok_or_else(|| anyhow!("Invalid rule {s}"))?;
+        let condition = Condition::parse(condition.trim(), attributes)?;
+        let next_step = NextStep::parse(next_step.trim(), names);
         Ok(Rule {
             condition,
             next_step,
@@ -271,162 +208,685 @@ impl FromStr for Rule {
     }
 }
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-struct Workflow {
-    name: String,
+pub struct Workflow {
     rules: Vec<Rule>,
     default_next_step: NextStep,
 }
 
-impl FromStr for Workflow {
-    type Err = color_eyre::Report;
-
-    fn from_str(s: &str) -> std::prelude::v1::Result<Self, Self::Err> {
-        let mut splits = s.split(&['{', '}']);
-        let name = splits.next().unwrap().trim().to_string();
-        let mut rules: Vec<String> = splits
-            .next()
-            .unwrap()
-            .split(',')
-            .map(String::from)
-            .collect();
+impl Workflow {
+    /// Parses a line like `px{a<2006:qkq,m>2090:A,rfg}`, returning the name too
+    fn parse<'a>(
+        s: &'a str,
+        attributes: &mut AttributeSet,
+        names: &mut GraphBuilder<Option<Workflow>>,
+    ) -> Result<(&'a str, Self)> {
+        let (name, rules) = s
+            .trim_end_matches('}')
+            .split_once('{')
+            .ok_or_else(|| anyhow!("Invalid workflow {s}"))?;
+        let name = name.trim();
+        let mut rules: Vec<&str> = rules.split(',').collect();
 
-        let default_next_step: NextStep = rules.pop().unwrap().trim().to_string().parse()?;
+        let default_next_step = NextStep::parse(
+            rules
+                .pop()
+                .ok_or_else(|| anyhow!("Workflow {name} has no rules"))?
+                .trim(),
+            names,
+        );
 
         let rules = rules
             .into_iter()
-            .map(|s| s.trim().parse())
+            .map(|s| Rule::parse(s.trim(), attributes, names))
             .collect::<Result<Vec<Rule>>>()?;
-        Ok(Workflow {
+        Ok((
             name,
-            rules,
-            default_next_step,
+            Workflow {
+                rules,
+                default_next_step,
+            },
+        ))
+    }
+
+    /// The step a part takes after going through the rules
+    fn next_step(&self, part: &Part) -> NextStep {
+        self.rules
+            .iter()
+            .find_map(|rule| rule.apply_rule(part))
+            .unwrap_or(self.default_next_step)
+    }
+}
+
+/// All workflows of the input by the id of their name. Names referenced by a
+/// rule without a definition have no workflow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkflowSet {
+    workflows: Graph<Option<Workflow>>,
+    start: Id,
+    attributes: AttributeSet,
+    /// Values every attribute can take, half open so the end can't overflow
+    bounds: Range<u64>,
+}
+
+/// Findings of [`WorkflowSet::analyse`], names are sorted
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct WorkflowAnalysis {
+    /// Workflows referenced by a rule but never defined
+    pub undefined: Vec<String>,
+    /// Workflows that can't be reached from [`START_WORKFLOW`]
+    pub unreachable: Vec<String>,
+    /// Loops between workflows, each starting from its smallest name
+    pub cycles: Vec<Vec<String>>,
+    /// Rules no part ever reaches, as workflow name and rule index. The
+    /// default step has the index `rules.len()`. Only computed for acyclic
+    /// workflow sets.
+    pub dead_rules: Vec<(String, usize)>,
+}
+
+impl WorkflowAnalysis {
+    fn is_sound(&self) -> bool {
+        self.undefined.is_empty() && self.cycles.is_empty()
+    }
+}
+
+pub const START_WORKFLOW: &str = "in";
+
+impl Workflow {
+    fn next_steps(&self) -> impl Iterator<Item = &NextStep> {
+        self.rules
+            .iter()
+            .map(|r| &r.next_step)
+            .chain(std::iter::once(&self.default_next_step))
+    }
+
+    fn next_steps_mut(&mut self) -> impl Iterator<Item = &mut NextStep> {
+        self.rules
+            .iter_mut()
+            .map(|r| &mut r.next_step)
+            .chain(std::iter::once(&mut self.default_next_step))
+    }
+
+    fn next_workflows(&self) -> impl Iterator<Item = Id> + '_ {
+        self.next_steps().filter_map(|step| match step {
+            NextStep::Workflow(id) => Some(*id),
+            _ => None,
         })
     }
 }
 
-pub fn solve_task_one(input: Vec<String>) -> Result<i64> {
-    let start_time = Instant::now();
-    let mut parts = input.split(|s| s.is_empty());
-    let workflows: HashMap<String, Workflow> = parts
-        .next()
-        .unwrap()
-        .into_iter()
-        .map(|s| {
-            let workflow: Workflow = s.parse().unwrap();
-            (workflow.name.clone(), workflow)
+impl WorkflowSet {
+    pub fn from_lines(lines: &[String]) -> Result<Self> {
+        Self::from_lines_with(lines, AttributeSet::default())
+    }
+
+    /// Parses the workflows, adding attributes named only in conditions to `attributes`
+    pub fn from_lines_with(lines: &[String], mut attributes: AttributeSet) -> Result<Self> {
+        let mut names = GraphBuilder::new();
+        let start = names.id(START_WORKFLOW);
+        for line in lines {
+            let (name, workflow) = Workflow::parse(line, &mut attributes, &mut names)?;
+            names.define(name, Some(workflow))?;
+        }
+        Ok(Self {
+            workflows: names.build_with(|_| None),
+            start,
+            attributes,
+            bounds: *DEFAULT_BOUNDS.start()..*DEFAULT_BOUNDS.end() + 1,
+        })
+    }
+
+    /// Fails for bounds ending at `u64::MAX`, the ranges can't hold the end
+    pub fn with_bounds(self, bounds: RangeInclusive<u64>) -> Result<Self> {
+        let end = bounds
+            .end()
+            .checked_add(1)
+            .ok_or_else(|| anyhow!("Bounds {bounds:?} have to end below {}", u64::MAX))?;
+        Ok(Self {
+            bounds: *bounds.start()..end,
+            ..self
         })
-        .collect();
+    }
 
-    let parts: Vec<Part> = parts
-        .next()
-        .unwrap()
-        .into_iter()
-        .map(|s| s.parse().unwrap())
-        .collect();
-    let mut accepted_parts = vec![];
-    for part in parts {
-        let mut current_workflow = NextStep::Workflow("in".to_string());
-        while let NextStep::Workflow(_) = &current_workflow {
-            current_workflow = current_workflow.apply_next_step(part, &workflows);
+    pub fn attributes(&self) -> &AttributeSet {
+        &self.attributes
+    }
+
+    /// Every part within the bounds of the workflow set
+    fn all_parts(&self) -> PartConstraint {
+        PartConstraint {
+            ranges: HyperRect::uniform(self.attributes.len(), self.bounds.clone()),
         }
-        if current_workflow == NextStep::Accepted {
-            accepted_parts.push(part);
+    }
+
+    fn defined(&self) -> impl Iterator<Item = (Id, &Workflow)> + '_ {
+        self.workflows
+            .iter()
+            .filter_map(|(id, workflow)| workflow.as_ref().map(|w| (id, w)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.defined().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.workflows.get(name).is_some_and(Option::is_some)
+    }
+
+    fn names(&self, ids: impl Iterator<Item = Id>) -> Vec<String> {
+        let mut names: Vec<String> = ids.map(|id| self.workflows.name(id).to_string()).collect();
+        names.sort();
+        names
+    }
+
+    /// Interprets the workflows for a single part
+    pub fn run(&self, part: &Part) -> Result<bool> {
+        let mut current_workflow = NextStep::Workflow(self.start);
+        let mut steps = 0;
+        while let NextStep::Workflow(id) = current_workflow {
+            let Some(workflow) = &self.workflows[id] else {
+                return Err(anyhow!("Undefined workflow {}", self.workflows.name(id)));
+            };
+            steps += 1;
+            if steps > self.workflows.len() {
+                return Err(anyhow!("Part {part:?} loops through the workflows"));
+            }
+            current_workflow = workflow.next_step(part);
+        }
+        Ok(current_workflow == NextStep::Accepted)
+    }
+
+    pub fn analyse(&self) -> WorkflowAnalysis {
+        let mut referenced = vec![false; self.workflows.len()];
+        for (_, workflow) in self.defined() {
+            for next in workflow.next_workflows() {
+                referenced[next.index()] = true;
+            }
+        }
+        let undefined = self.names(
+            self.workflows
+                .iter()
+                .filter(|(id, workflow)| workflow.is_none() && referenced[id.index()])
+                .map(|(id, _)| id),
+        );
+
+        let mut reachable = vec![false; self.workflows.len()];
+        let mut queue = VecDeque::from([self.start]);
+        while let Some(id) = queue.pop_front() {
+            if std::mem::replace(&mut reachable[id.index()], true) {
+                continue;
+            }
+            if let Some(workflow) = &self.workflows[id] {
+                queue.extend(workflow.next_workflows());
+            }
+        }
+        let unreachable = self.names(
+            self.defined()
+                .filter(|(id, _)| !reachable[id.index()])
+                .map(|(id, _)| id),
+        );
+
+        let mut analysis = WorkflowAnalysis {
+            undefined,
+            unreachable,
+            cycles: self.find_cycles(),
+            dead_rules: vec![],
+        };
+        if analysis.is_sound() {
+            analysis.dead_rules = self.find_dead_rules();
         }
+        analysis
     }
-    let sol = accepted_parts
+
+    /// Depth first search reporting every back edge as a cycle
+    fn find_cycles(&self) -> Vec<Vec<String>> {
+        fn visit(
+            set: &WorkflowSet,
+            id: Id,
+            path: &mut Vec<Id>,
+            done: &mut [bool],
+            cycles: &mut Vec<Vec<String>>,
+        ) {
+            if let Some(start) = path.iter().position(|n| *n == id) {
+                let mut cycle: Vec<String> = path[start..]
+                    .iter()
+                    .map(|n| set.workflows.name(*n).to_string())
+                    .collect();
+                let smallest = (0..cycle.len()).min_by_key(|i| &cycle[*i]).unwrap_or(0);
+                cycle.rotate_left(smallest);
+                if !cycles.contains(&cycle) {
+                    cycles.push(cycle);
+                }
+                return;
+            }
+            if done[id.index()] {
+                return;
+            }
+            let Some(workflow) = &set.workflows[id] else {
+                return;
+            };
+            path.push(id);
+            for next in workflow.next_workflows() {
+                visit(set, next, path, done, cycles);
+            }
+            path.pop();
+            done[id.index()] = true;
+        }
+
+        let mut done = vec![false; self.workflows.len()];
+        let mut cycles = vec![];
+        for (id, _) in self.defined() {
+            visit(self, id, &mut vec![], &mut done, &mut cycles);
+        }
+        cycles.sort();
+        cycles
+    }
+
+    /// Pushes every possible part through the workflows and reports the rules
+    /// of reachable workflows that never receive any of them
+    fn find_dead_rules(&self) -> Vec<(String, usize)> {
+        let mut reached = HashSet::new();
+        let mut visited = vec![false; self.workflows.len()];
+        let mut constraints = VecDeque::from([(self.all_parts(), NextStep::Workflow(self.start))]);
+        while let Some((constraint, next_step)) = constraints.pop_front() {
+            let NextStep::Workflow(id) = next_step else {
+                continue;
+            };
+            let Some(workflow) = &self.workflows[id] else {
+                continue;
+            };
+            for (index, constraint, next_step) in workflow.route_part_constraint(constraint) {
+                reached.insert((id, index));
+                constraints.push_back((constraint, next_step));
+            }
+            visited[id.index()] = true;
+        }
+        let mut dead_rules: Vec<(String, usize)> = self
+            .defined()
+            .filter(|(id, _)| visited[id.index()])
+            .flat_map(|(id, workflow)| (0..=workflow.rules.len()).map(move |i| (id, i)))
+            .filter(|rule| !reached.contains(rule))
+            .map(|(id, i)| (self.workflows.name(id).to_string(), i))
+            .collect();
+        dead_rules.sort();
+        dead_rules
+    }
+
+    /// Returns an equivalent workflow set without dead rules, unreachable
+    /// workflows, trailing rules that lead to the default step anyway and
+    /// workflows that always lead to the same step
+    pub fn simplify(&self) -> Self {
+        let mut set = self.clone();
+        loop {
+            let mut changed = false;
+            let analysis = set.analyse();
+            if analysis.is_sound() {
+                for (name, index) in analysis.dead_rules.iter().rev() {
+                    let workflow = set
+                        .workflows
+                        .id(name)
+                        .and_then(|id| set.workflows[id].as_mut())
+                        .expect("dead rules are defined");
+                    if *index < workflow.rules.len() {
+                        workflow.rules.remove(*index);
+                    } else if let Some(last) = workflow.rules.pop() {
+                        // Parts never get past the last rule, so it acts as the default
+                        workflow.default_next_step = last.next_step;
+                    }
+                    changed = true;
+                }
+                for name in &analysis.unreachable {
+                    if let Some(id) = set.workflows.id(name) {
+                        set.workflows[id] = None;
+                        changed = true;
+                    }
+                }
+            }
+
+            for (_, workflow) in set.workflows.iter_mut() {
+                let Some(workflow) = workflow else {
+                    continue;
+                };
+                while workflow
+                    .rules
+                    .last()
+                    .is_some_and(|r| r.next_step == workflow.default_next_step)
+                {
+                    workflow.rules.pop();
+                    changed = true;
+                }
+            }
+
+            let aliases: Vec<Option<NextStep>> = set
+                .workflows
+                .iter()
+                .map(|(id, workflow)| match workflow {
+                    Some(w)
+                        if w.rules.is_empty()
+                            && id != set.start
+                            && w.default_next_step != NextStep::Workflow(id) =>
+                    {
+                        Some(w.default_next_step)
+                    }
+                    _ => None,
+                })
+                .collect();
+            // Replace one level of aliases per pass so loops of aliases can't hang
+            for (_, workflow) in set.workflows.iter_mut() {
+                for next_step in workflow.iter_mut().flat_map(|w| w.next_steps_mut()) {
+                    if let NextStep::Workflow(id) = next_step {
+                        if let Some(target) = aliases[id.index()] {
+                            *next_step = target;
+                            changed = true;
+                        }
+                    }
+                }
+            }
+
+            if !changed {
+                return set;
+            }
+        }
+    }
+
+    /// Compiles the workflows into a decision tree, workflows reached from
+    /// several rules are shared between the branches
+    pub fn compile(&self) -> Result<DecisionTree> {
+        let analysis = self.analyse();
+        if !analysis.undefined.is_empty() {
+            return Err(anyhow!("Undefined workflows {:?}", analysis.undefined));
+        }
+        if !analysis.cycles.is_empty() {
+            return Err(anyhow!("Workflows contain cycles {:?}", analysis.cycles));
+        }
+        if !self.contains(START_WORKFLOW) {
+            return Err(anyhow!("Undefined workflow {START_WORKFLOW}"));
+        }
+
+        fn compile_step(
+            set: &WorkflowSet,
+            step: NextStep,
+            nodes: &mut Vec<DecisionNode>,
+            compiled: &mut [Option<usize>],
+        ) -> usize {
+            match step {
+                NextStep::Accepted => DecisionTree::ACCEPT,
+                NextStep::Rejected => DecisionTree::REJECT,
+                NextStep::Workflow(id) => {
+                    if let Some(node) = compiled[id.index()] {
+                        return node;
+                    }
+                    let workflow = set.workflows[id]
+                        .as_ref()
+                        .expect("the analysis found no undefined workflows");
+                    let mut node = compile_step(set, workflow.default_next_step, nodes, compiled);
+                    for rule in workflow.rules.iter().rev() {
+                        let if_true = compile_step(set, rule.next_step, nodes, compiled);
+                        nodes.push(DecisionNode::Test {
+                            condition: rule.condition,
+                            if_true,
+                            if_false: node,
+                        });
+                        node = nodes.len() - 1;
+                    }
+                    compiled[id.index()] = Some(node);
+                    node
+                }
+            }
+        }
+
+        let mut nodes = vec![DecisionNode::Accept, DecisionNode::Reject];
+        let root = compile_step(
+            self,
+            NextStep::Workflow(self.start),
+            &mut nodes,
+            &mut vec![None; self.workflows.len()],
+        );
+        Ok(DecisionTree {
+            nodes,
+            root,
+            attributes: self.attributes.clone(),
+            all_parts: self.all_parts(),
+        })
+    }
+}
+
+/// Writes the workflows back in the syntax of the input
+impl Display for WorkflowSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let step = |step: &NextStep| match step {
+            NextStep::Workflow(id) => self.workflows.name(*id),
+            NextStep::Accepted => "A",
+            NextStep::Rejected => "R",
+        };
+        for (id, workflow) in self.defined() {
+            write!(f, "{}{{", self.workflows.name(id))?;
+            for rule in &workflow.rules {
+                let (attribute, operator, value) = match rule.condition {
+                    Condition::GreaterThan(attribute, value) => (attribute, '>', value),
+                    Condition::LessThan(attribute, value) => (attribute, '<', value),
+                };
+                let name = &self.attributes.names()[attribute.0];
+                write!(f, "{name}{operator}{value}:{},", step(&rule.next_step))?;
+            }
+            writeln!(f, "{}}}", step(&workflow.default_next_step))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecisionNode {
+    Accept,
+    Reject,
+    Test {
+        condition: Condition,
+        if_true: usize,
+        if_false: usize,
+    },
+}
+
+/// Workflows flattened into nodes referring to each other by index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecisionTree {
+    nodes: Vec<DecisionNode>,
+    root: usize,
+    attributes: AttributeSet,
+    all_parts: PartConstraint,
+}
+
+impl DecisionTree {
+    const ACCEPT: usize = 0;
+    const REJECT: usize = 1;
+
+    pub fn nodes(&self) -> &[DecisionNode] {
+        &self.nodes
+    }
+
+    pub fn classify(&self, part: &Part) -> bool {
+        let mut node = self.root;
+        loop {
+            match self.nodes[node] {
+                DecisionNode::Accept => return true,
+                DecisionNode::Reject => return false,
+                DecisionNode::Test {
+                    condition,
+                    if_true,
+                    if_false,
+                } => {
+                    node = if condition.apply_condition(part) {
+                        if_true
+                    } else {
+                        if_false
+                    };
+                }
+            }
+        }
+    }
+
+    /// Splits all parts within the bounds into the disjoint regions of accepted parts
+    pub fn accepted_regions(&self) -> AcceptedRegions {
+        let mut regions = vec![];
+        let mut stack = vec![(self.root, self.all_parts.clone())];
+        while let Some((node, constraint)) = stack.pop() {
+            match self.nodes[node] {
+                DecisionNode::Accept => regions.push(constraint),
+                DecisionNode::Reject => {}
+                DecisionNode::Test {
+                    condition,
+                    if_true,
+                    if_false,
+                } => {
+                    let (matching, remaining) = condition.split_constraint(&constraint);
+                    stack.extend(remaining.map(|c| (if_false, c)));
+                    stack.extend(matching.map(|c| (if_true, c)));
+                }
+            }
+        }
+        AcceptedRegions {
+            attributes: self.attributes.clone(),
+            regions,
+        }
+    }
+}
+
+/// Disjoint boxes of attribute ranges covering every accepted part
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AcceptedRegions {
+    attributes: AttributeSet,
+    regions: Vec<PartConstraint>,
+}
+
+impl AcceptedRegions {
+    pub fn regions(&self) -> &[PartConstraint] {
+        &self.regions
+    }
+
+    /// Number of distinct accepted parts
+    pub fn total(&self) -> Result<u128> {
+        self.regions
+            .iter()
+            .map(|r| r.total_acceptable_parts())
+            .try_sum()
+    }
+}
+
+impl Display for AcceptedRegions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for region in &self.regions {
+            let ranges: Vec<String> = self
+                .attributes
+                .names()
+                .iter()
+                .zip(region.ranges.axes())
+                .map(|(name, range)| format!("{name}={}..={}", range.start, range.end - 1))
+                .collect();
+            writeln!(f, "{{{}}}", ranges.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+pub fn parse_input(input: &[String]) -> Result<(WorkflowSet, Vec<Part>)> {
+    let mut blocks = input.split(|s| s.is_empty());
+    let workflow_lines = blocks.next().unwrap_or_default();
+    let part_lines = blocks.next().unwrap_or_default();
+    // The attribute order follows the parts, attributes only used in rules come last
+    let attributes = match part_lines.first() {
+        Some(part) => AttributeSet::from_part(part)?,
+        None => AttributeSet::default(),
+    };
+    let workflows = WorkflowSet::from_lines_with(workflow_lines, attributes)?;
+    let parts = part_lines
         .iter()
+        .map(|s| Part::parse(s, workflows.attributes()))
+        .collect::<Result<Vec<Part>>>()?;
+    Ok((workflows, parts))
+}
+
+pub fn solve_task_one(input: Vec<String>) -> Result<Answer> {
+    let start_time = Instant::now();
+    let (workflows, parts) = parse_input(&input)?;
+    // Simplifying assumes every part lies within the bounds, which parts
+    // aren't checked against, so sort them by the workflows as written
+    let decision_tree = workflows.compile()?;
+
+    let sol = parts
+        .iter()
+        .filter(|part| decision_tree.classify(part))
         .map(|p| p.total_rating())
-        .sum::<usize>();
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .try_sum()?;
 
     eprintln!("{:?}", Instant::now() - start_time);
-    Ok(sol as i64)
+    Ok(sol.into())
 }
 
+/// Half-open range of values per attribute, in the order of the [`AttributeSet`]
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-struct PartConstraint {
-    x: Range<i64>,
-    m: Range<i64>,
-    a: Range<i64>,
-    s: Range<i64>,
+pub struct PartConstraint {
+    ranges: HyperRect<u64>,
 }
+
 impl PartConstraint {
-    fn total_acceptable_parts(&self) -> usize {
-        self.x.clone().count()
-            * self.m.clone().count()
-            * self.a.clone().count()
-            * self.s.clone().count()
+    pub fn range(&self, attribute: Attribute) -> RangeInclusive<u64> {
+        let range = self.ranges.axis(attribute.0);
+        range.start..=range.end - 1
+    }
+
+    pub fn total_acceptable_parts(&self) -> u128 {
+        self.ranges.volume()
     }
 }
 
 impl Workflow {
-    fn apply_part_constraint(&self, constraint: PartConstraint) -> Vec<(PartConstraint, NextStep)> {
+    /// Splits the constraint between the rules of the workflow, returning the
+    /// index of the rule each piece leaves through with its next step
+    fn route_part_constraint(
+        &self,
+        constraint: PartConstraint,
+    ) -> Vec<(usize, PartConstraint, NextStep)> {
         let mut results = vec![];
         let mut current_constraint = constraint;
-        for rule in &self.rules {
+        for (index, rule) in self.rules.iter().enumerate() {
             let (result, remaining_constraint) =
                 rule.apply_rule_part_constraint(&current_constraint);
             if let Some((new_constraint, next_step)) = result {
-                results.push((new_constraint, next_step));
+                results.push((index, new_constraint, next_step));
+            }
+            match remaining_constraint {
+                Some(remaining_constraint) => current_constraint = remaining_constraint,
+                None => return results,
             }
-            current_constraint = remaining_constraint;
         }
-        results.push((current_constraint, self.default_next_step.clone()));
+        results.push((self.rules.len(), current_constraint, self.default_next_step));
         results
     }
 }
 
-impl Default for PartConstraint {
-    fn default() -> Self {
-        Self {
-            x: MIN_ATTRIBUTE_VALUE..MAX_ATTRIBUTE_VALUE,
-            m: MIN_ATTRIBUTE_VALUE..MAX_ATTRIBUTE_VALUE,
-            a: MIN_ATTRIBUTE_VALUE..MAX_ATTRIBUTE_VALUE,
-            s: MIN_ATTRIBUTE_VALUE..MAX_ATTRIBUTE_VALUE,
-        }
-    }
-}
+/// Attribute values of the puzzle range from 1 to 4000
+pub const DEFAULT_BOUNDS: RangeInclusive<u64> = 1..=4000;
 
-const MIN_ATTRIBUTE_VALUE: i64 = 1;
-const MAX_ATTRIBUTE_VALUE: i64 = 4001;
+/// Accepted regions of the workflows in `input` for attribute values within `bounds`
+pub fn find_accepted_regions(
+    input: &[String],
+    bounds: RangeInclusive<u64>,
+) -> Result<AcceptedRegions> {
+    let (workflows, _) = parse_input(input)?;
+    let decision_tree = workflows.with_bounds(bounds)?.simplify().compile()?;
+    Ok(decision_tree.accepted_regions())
+}
 
-pub fn solve_task_two(#[allow(unused_variables)] input: Vec<String>) -> Result<i64> {
+pub fn solve_task_two(input: Vec<String>) -> Result<Answer> {
     let start_time = Instant::now();
-    let mut parts = input.split(|s| s.is_empty());
-    let workflows: HashMap<String, Workflow> = parts
-        .next()
-        .unwrap()
-        .into_iter()
-        .map(|s| {
-            let workflow: Workflow = s.parse().unwrap();
-            (workflow.name.clone(), workflow)
-        })
-        .collect();
-
-    let mut constraints = VecDeque::from(vec![(
-        PartConstraint::default(),
-        NextStep::Workflow("in".to_string()),
-    )]);
-    let mut accepted_constraints = vec![];
-    while let Some((constraint, next_step)) = constraints.pop_front() {
-        if let NextStep::Accepted = next_step {
-            accepted_constraints.push(constraint);
-        } else if let NextStep::Workflow(workflow) = next_step {
-            let workflow = workflows.get(&workflow).unwrap();
-            let mut new_constraints = workflow
-                .apply_part_constraint(constraint)
-                .try_into()
-                .unwrap();
-
-            // let mut new_constraints = VecDeque::from(new_constraints);
-            constraints.append(&mut new_constraints);
-        }
-    }
-    let sol = accepted_constraints
-        .iter()
-        .map(|c| c.total_acceptable_parts())
-        .sum::<usize>();
+    let regions = find_accepted_regions(&input, DEFAULT_BOUNDS)?;
+    let sol = regions.total()?;
     eprintln!("{:?}", Instant::now() - start_time);
-    Ok(sol as i64)
+    Ok(sol.into())
 }
 
 #[cfg(test)]
@@ -446,7 +906,10 @@ mod test {
         Ok(lines?)
     }
 
-    use crate::{solve_task_one, solve_task_two};
+    use crate::{
+        find_accepted_regions, parse_input, solve_task_one, solve_task_two, WorkflowAnalysis,
+        WorkflowSet, DEFAULT_BOUNDS,
+    };
 
     #[test]
     fn test_case_one_example() -> Result<()> {
@@ -464,6 +927,14 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_case_one_out_of_bounds() -> Result<()> {
+        // The rule never applies within the bounds, but does to this part
+        let lines = ["in{x>4000:R,A}", "", "{x=5000,m=1,a=1,s=1}"].map(String::from);
+        assert_eq!(solve_task_one(lines.to_vec())?, 0);
+        Ok(())
+    }
+
     #[test]
     fn test_case_two_example() -> Result<()> {
         let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -479,4 +950,92 @@ mod test {
         assert_eq!(solve_task_two(file)?, 126107942006821);
         Ok(())
     }
+
+    #[test]
+    fn test_compiled_matches_interpreter() -> Result<()> {
+        let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let file = get_file(cargo_manifest_dir.join("inputs/full.txt"))?;
+        let (workflows, parts) = parse_input(&file)?;
+        let simplified = workflows.simplify();
+        assert!(simplified.len() < workflows.len());
+        let decision_tree = simplified.compile()?;
+        for part in &parts {
+            assert_eq!(decision_tree.classify(part), workflows.run(part)?);
+            assert_eq!(simplified.run(part)?, workflows.run(part)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_analyse() -> Result<()> {
+        let lines = [
+            "in{x>10:a,x>5:b,R}",
+            "a{x<5:R,A}",
+            "b{m>1:A,A}",
+            "c{A}",
+            "d{x>1:e,R}",
+            "e{s<10:d,f}",
+        ]
+        .map(String::from);
+        let workflows = WorkflowSet::from_lines(&lines)?;
+        assert_eq!(
+            workflows.analyse(),
+            WorkflowAnalysis {
+                undefined: vec!["f".to_string()],
+                unreachable: vec!["c".to_string(), "d".to_string(), "e".to_string()],
+                cycles: vec![vec!["d".to_string(), "e".to_string()]],
+                dead_rules: vec![],
+            }
+        );
+        assert!(workflows.compile().is_err());
+
+        let workflows = WorkflowSet::from_lines(&lines[..4])?;
+        let analysis = workflows.analyse();
+        assert_eq!(analysis.dead_rules, vec![("a".to_string(), 0)]);
+        assert_eq!(analysis.unreachable, vec!["c".to_string()]);
+
+        // a always accepts and so does b
+        let simplified = workflows.simplify();
+        assert_eq!(simplified.to_string(), "in{x>10:A,x>5:A,R}\n");
+        assert_eq!(simplified.compile()?.nodes().len(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_accepted_regions() -> Result<()> {
+        let lines = ["in{b<3:A,c>2:A,R}", "", "{c=1,b=2}"].map(String::from);
+        let regions = find_accepted_regions(&lines, 1..=4)?;
+        assert_eq!(regions.total()?, 2 * 4 + 2 * 2);
+        assert_eq!(
+            regions.to_string(),
+            "{c=1..=4,b=1..=2}\n{c=3..=4,b=3..=4}\n"
+        );
+
+        let regions = find_accepted_regions(&lines[..1], 1..=4)?;
+        assert_eq!(
+            regions.to_string(),
+            "{b=1..=2,c=1..=4}\n{b=3..=4,c=3..=4}\n"
+        );
+
+        assert!(parse_input(&["in{q>1:A,R}", "", "{c=1,b=2}"].map(String::from)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_large_bounds_do_not_overflow() -> Result<()> {
+        let lines = ["in{x>0:A,m>0:A,R}".to_string()];
+        let max = 1 << 62;
+        let regions = find_accepted_regions(&lines, 0..=max)?;
+        let values = max as u128 + 1;
+        assert_eq!(regions.total()?, values * values - 1);
+        assert_eq!(
+            regions.regions()[0].range(regions.attributes.get("x").unwrap()),
+            1..=max
+        );
+
+        assert!(find_accepted_regions(&lines, 0..=u64::MAX).is_err());
+        let lines = [format!("in{{x>{}:A,R}}", u64::MAX)];
+        assert!(find_accepted_regions(&lines, DEFAULT_BOUNDS).is_err());
+        Ok(())
+    }
 }
@@ -1,6 +1,7 @@
 use std::str::FromStr;
 
 use color_eyre::{eyre::anyhow, Result};
+use common::answer::{Answer, Checked, CheckedIterator};
 #[derive(Debug)]
 pub enum GameCube {
     Red,
@@ -19,7 +20,7 @@ impl FromStr for GameCube {
         }
     }
 }
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct RoundData {
     pub red_cubes: i32,
     pub green_cubes: i32,
@@ -31,18 +32,10 @@ impl RoundData {
         self.red_cubes <= r && self.green_cubes <= g && self.blue_cubes <= b
     }
 
-    pub fn pow(&self) -> i32 {
-        self.red_cubes * self.green_cubes * self.blue_cubes
-    }
-}
-
-impl Default for RoundData {
-    fn default() -> Self {
-        RoundData {
-            red_cubes: 0,
-            green_cubes: 0,
-            blue_cubes: 0,
-        }
+    pub fn pow(&self) -> Result<i64> {
+        (self.red_cubes as i64)
+            .try_mul(self.green_cubes as i64)?
+            .try_mul(self.blue_cubes as i64)
     }
 }
 
@@ -51,11 +44,11 @@ impl FromStr for RoundData {
 
     fn from_str(s: &str) -> std::prelude::v1::Result<Self, Self::Err> {
         let mut initial_rd = RoundData::default();
-        s.split(",").try_for_each(|s| {
-            let parts = s.trim().split_whitespace().collect::<Vec<_>>();
+        s.split(',').try_for_each(|s| {
+            let parts = s.split_whitespace().collect::<Vec<_>>();
             let (balls, color) = (
                 parts
-                    .get(0)
+                    .first()
                     .ok_or(anyhow!("No number part"))?
                     .parse::<i32>()?,
                 parts
@@ -83,10 +76,10 @@ impl FromStr for GameData {
     type Err = color_eyre::Report;
 
     fn from_str(s: &str) -> std::prelude::v1::Result<Self, Self::Err> {
-        let game_str_parts: Vec<&str> = s.split(":").collect();
+        let game_str_parts: Vec<&str> = s.split(':').collect();
         let id = game_str_parts[0].replace("Game ", "").parse::<i32>()?;
         let rounds = game_str_parts[1]
-            .split(";")
+            .split(';')
             .map(RoundData::from_str)
             .collect::<Result<Vec<_>>>()?;
         Ok(GameData { id, rounds })
@@ -113,35 +106,29 @@ static MAX_RED_CUBES: i32 = 12;
 static MAX_GREEN_CUBES: i32 = 13;
 static MAX_BLUE_CUBES: i32 = 14;
 
-pub fn solve_task_one(input: Vec<String>) -> Result<i32> {
-    let sum: i32 = input
+pub fn solve_task_one(input: Vec<String>) -> Result<Answer> {
+    let sum = input
         .into_iter()
-        .filter_map(|line| match line.parse::<GameData>() {
-            Ok(gd) => Some(gd),
-            Err(_) => None,
-        })
+        .filter_map(|line| line.parse::<GameData>().ok())
         .filter_map(|gd| {
             if gd.satisfies_constraints(MAX_RED_CUBES, MAX_GREEN_CUBES, MAX_BLUE_CUBES) {
-                Some(gd.id)
+                Some(gd.id as i64)
             } else {
                 None
             }
         })
-        .sum();
-    Ok(sum)
+        .try_sum()?;
+    Ok(sum.into())
 }
 
-pub fn solve_task_two(input: Vec<String>) -> Result<i32> {
-    let sum: i32 = input
+pub fn solve_task_two(input: Vec<String>) -> Result<Answer> {
+    let powers = input
         .into_iter()
-        .filter_map(|line| match line.parse::<GameData>() {
-            Ok(gd) => Some(gd),
-            Err(_) => None,
-        })
+        .filter_map(|line| line.parse::<GameData>().ok())
         .filter_map(|gd| gd.minimal_possible_cubes())
         .map(|rd| rd.pow())
-        .sum();
-    Ok(sum)
+        .collect::<Result<Vec<_>>>()?;
+    Ok(powers.into_iter().try_sum()?.into())
 }
 
 #[cfg(test)]
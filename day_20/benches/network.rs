@@ -0,0 +1,28 @@
+use std::path::PathBuf;
+
+use common::get_file;
+use criterion::{criterion_group, criterion_main, Criterion};
+use solution::Network;
+
+fn bench_network(c: &mut Criterion) {
+    let input = get_file(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("inputs/full.txt"))
+        .expect("the personal input exists");
+    c.bench_function("parse", |b| b.iter(|| Network::from_lines(&input).unwrap()));
+    c.bench_function("1000 presses", |b| {
+        b.iter(|| {
+            let mut network = Network::from_lines(&input).unwrap();
+            for _ in 0..1000 {
+                network.press_button_with(|_| {}).unwrap();
+            }
+        })
+    });
+    c.bench_function("feeder cycles", |b| {
+        b.iter(|| {
+            let mut network = Network::from_lines(&input).unwrap();
+            network.analyse_feeder_cycles("rx", 100_000).unwrap()
+        })
+    });
+}
+
+criterion_group!(benches, bench_network);
+criterion_main!(benches);
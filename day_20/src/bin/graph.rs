@@ -0,0 +1,35 @@
+use clap::{Parser, ValueEnum};
+use color_eyre::Result;
+
+use common::CommonCli;
+use solution::Network;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Dot,
+    Mermaid,
+}
+
+/// Prints the module network of a day 20 input as a graph
+#[derive(Parser)]
+struct Args {
+    #[command(flatten)]
+    input: CommonCli,
+    #[arg(long, value_enum, default_value_t = Format::Dot)]
+    format: Format,
+    /// Module whose feeder subgraphs get highlighted
+    #[arg(long, default_value = "rx")]
+    target: String,
+}
+
+fn main() -> Result<()> {
+    color_eyre::install()?;
+    let args = Args::parse();
+    let file = args.input.read_input(env!("CARGO_MANIFEST_DIR"))?;
+    let network = Network::from_lines(&file)?;
+    match args.format {
+        Format::Dot => print!("{}", network.to_dot(&args.target)),
+        Format::Mermaid => print!("{}", network.to_mermaid(&args.target)),
+    }
+    Ok(())
+}
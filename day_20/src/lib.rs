@@ -1,24 +1,46 @@
-use std::{
-    collections::{HashMap, VecDeque},
-    str::FromStr,
-    time::Instant,
-};
+use std::{collections::VecDeque, fmt::Display, str::FromStr, time::Instant};
 
-use color_eyre::Result;
-use num::Integer;
+use color_eyre::{eyre::anyhow, Result};
+use common::{
+    answer::{Answer, Checked},
+    graph::{Graph, GraphBuilder, Id},
+    math::{crt, lcm},
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Pulse {
+pub enum Pulse {
     High,
     Low,
 }
 
+impl Display for Pulse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pulse::High => write!(f, "high"),
+            Pulse::Low => write!(f, "low"),
+        }
+    }
+}
+
+/// A single pulse travelling along an edge of the network, see
+/// [`Network::describe`] for the module names
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PulseEvent {
+    pub from: Id,
+    pub to: Id,
+    pub pulse: Pulse,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 enum ModuleType {
     Broadcast,
     FlipFlop(bool),
-    Conjunction(HashMap<String, Pulse>),
-    Output,
+    /// Last pulse received from every input, indexed by the input's slot,
+    /// and how many of them are high
+    Conjunction {
+        inputs: Vec<Pulse>,
+        high: usize,
+    },
 }
 
 impl FromStr for ModuleType {
@@ -28,236 +50,561 @@ impl FromStr for ModuleType {
         match &s[..1] {
             "b" => Ok(Self::Broadcast),
             "%" => Ok(Self::FlipFlop(false)),
-            "&" => Ok(Self::Conjunction(HashMap::new())),
-            _ => Err(color_eyre::eyre::anyhow!("Invalid module type")),
+            "&" => Ok(Self::Conjunction {
+                inputs: vec![],
+                high: 0,
+            }),
+            _ => Err(anyhow!("Invalid module type")),
         }
     }
 }
 
+/// Connection to a destination, which knows its inputs by slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Edge {
+    to: Id,
+    slot: usize,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct Module {
-    name: String,
     module_type: ModuleType,
-    destinations: VecDeque<String>,
+    destinations: Vec<Edge>,
 }
 
 impl Module {
-    fn process_pulse(&mut self, pulse: Pulse, from: &String) -> VecDeque<(String, String, Pulse)> {
+    /// Returns the pulse the module sends to all of its destinations, if any
+    fn process_pulse(&mut self, pulse: Pulse, slot: usize) -> Option<Pulse> {
         match self.module_type {
-            ModuleType::Broadcast => self
-                .destinations
-                .iter()
-                .map(|d| (self.name.clone(), d.clone(), Pulse::Low))
-                .collect(),
+            ModuleType::Broadcast => Some(pulse),
             ModuleType::FlipFlop(ref mut state) => {
                 if pulse == Pulse::Low {
                     *state = !*state;
-                    self.destinations
-                        .iter()
-                        .map(|d| {
-                            (
-                                self.name.clone(),
-                                d.clone(),
-                                if *state { Pulse::High } else { Pulse::Low },
-                            )
-                        })
-                        .collect()
+                    Some(if *state { Pulse::High } else { Pulse::Low })
                 } else {
-                    VecDeque::new()
+                    None
                 }
             }
-            ModuleType::Conjunction(ref mut states) => {
-                let _last_state = states.get(from).expect(&format!("Invalid state {}", from));
-                states.insert(from.to_string(), pulse);
-                if states.values().all(|s| *s == Pulse::High) {
-                    self.destinations
-                        .iter()
-                        .map(|d| (self.name.clone(), d.clone(), Pulse::Low))
-                        .collect()
+            ModuleType::Conjunction {
+                ref mut inputs,
+                ref mut high,
+            } => {
+                match (inputs[slot], pulse) {
+                    (Pulse::Low, Pulse::High) => *high += 1,
+                    (Pulse::High, Pulse::Low) => *high -= 1,
+                    _ => {}
+                }
+                inputs[slot] = pulse;
+                if *high == inputs.len() {
+                    Some(Pulse::Low)
                 } else {
-                    self.destinations
-                        .iter()
-                        .map(|d| (self.name.clone(), d.clone(), Pulse::High))
-                        .collect()
+                    Some(Pulse::High)
                 }
             }
-            ModuleType::Output => VecDeque::new(),
         }
     }
 }
 
-impl FromStr for Module {
-    type Err = color_eyre::Report;
+/// Splits a line like `%a -> b, c` into the name, type and destinations
+fn parse_module(s: &str) -> Result<(&str, ModuleType, Vec<&str>)> {
+    let (module_type_src, destinations) = s
+        .split_once(" -> ")
+        .ok_or_else(|| anyhow!("Invalid module: {s}"))?;
+    let module_type = ModuleType::from_str(module_type_src)?;
+    let name = match module_type {
+        ModuleType::Broadcast => module_type_src,
+        _ => &module_type_src[1..],
+    };
+    Ok((name, module_type, destinations.split(", ").collect()))
+}
 
-    fn from_str(s: &str) -> std::prelude::v1::Result<Self, Self::Err> {
-        let mut split = s.split(" -> ");
-        let module_type_src = split.next().unwrap();
-        let module_type = ModuleType::from_str(module_type_src)?;
-        let name = match module_type {
-            ModuleType::Broadcast => "broadcast".to_string(),
-            _ => module_type_src[1..].to_string(),
-        };
-        let destinations = split
-            .next()
-            .unwrap()
-            .split(", ")
-            .map(String::from)
-            .collect();
+/// Records the button presses on which a given pulse travelled along an edge,
+/// each press at most once
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdgeWatch {
+    pub from: Id,
+    pub to: Id,
+    pub pulse: Pulse,
+    pub hits: Vec<usize>,
+}
+
+/// Identifies an [`EdgeWatch`] registered on a [`Network`]
+pub type WatchId = usize;
+
+/// The module graph, built once from the puzzle input, with the state of
+/// every flip-flop and conjunction. Destinations without a definition, like
+/// `rx`, have no module.
+#[derive(Debug, Clone)]
+pub struct Network {
+    modules: Graph<Option<Module>>,
+    /// Senders of every module, a pulse's slot is its sender's index here
+    inputs: Vec<Vec<Id>>,
+    button: Id,
+    broadcaster: Id,
+    presses: usize,
+    watches: Vec<EdgeWatch>,
+}
+
+impl Network {
+    pub const BROADCASTER: &'static str = "broadcaster";
+    pub const BUTTON: &'static str = "button";
+
+    pub fn from_lines(lines: &[String]) -> Result<Self> {
+        let mut builder = GraphBuilder::new();
+        let button = builder.id(Self::BUTTON);
+        for line in lines {
+            let (name, module_type, destinations) = parse_module(line)?;
+            let destinations = destinations
+                .into_iter()
+                .map(|d| Edge {
+                    to: builder.id(d),
+                    slot: 0,
+                })
+                .collect();
+            builder.define(
+                name,
+                Some(Module {
+                    module_type,
+                    destinations,
+                }),
+            )?;
+        }
+        let mut modules = builder.build_with(|_| None);
+        let broadcaster = modules
+            .id(Self::BROADCASTER)
+            .filter(|id| modules[*id].is_some())
+            .ok_or_else(|| anyhow!("No {} module", Self::BROADCASTER))?;
+
+        let mut inputs: Vec<Vec<Id>> = vec![vec![]; modules.len()];
+        for (id, module) in modules.iter_mut() {
+            for edge in module.iter_mut().flat_map(|m| &mut m.destinations) {
+                edge.slot = inputs[edge.to.index()].len();
+                inputs[edge.to.index()].push(id);
+            }
+        }
+        for (id, module) in modules.iter_mut() {
+            if let Some(Module {
+                module_type: ModuleType::Conjunction { inputs: states, .. },
+                ..
+            }) = module
+            {
+                *states = vec![Pulse::Low; inputs[id.index()].len()];
+            }
+        }
         Ok(Self {
-            name,
-            module_type,
-            destinations,
+            modules,
+            inputs,
+            button,
+            broadcaster,
+            presses: 0,
+            watches: vec![],
         })
     }
-}
 
-pub fn solve_task_one(#[allow(unused_variables)] input: Vec<String>) -> Result<i32> {
-    let start_time = Instant::now();
-    let modules = input
-        .iter()
-        .map(|s| Module::from_str(s))
-        .map(|m| m.map(|m| (m.name.clone(), m)))
-        .collect::<Result<HashMap<_, _>>>()?;
-    let connections: HashMap<String, VecDeque<String>> = modules
-        .values()
-        .filter(|m| matches!(m.module_type, ModuleType::Conjunction(_)))
-        // .map(|m| (m, VecDeque::new()))
-        .map(|module| {
-            let froms = modules
+    /// Number of times the button has been pressed
+    pub fn presses(&self) -> usize {
+        self.presses
+    }
+
+    pub fn name(&self, id: Id) -> &str {
+        self.modules.name(id)
+    }
+
+    /// The event as in the puzzle, like `a -high-> b`
+    pub fn describe(&self, event: &PulseEvent) -> String {
+        format!(
+            "{} -{}-> {}",
+            self.name(event.from),
+            event.pulse,
+            self.name(event.to)
+        )
+    }
+
+    fn id(&self, name: &str) -> Result<Id> {
+        self.modules
+            .id(name)
+            .ok_or_else(|| anyhow!("No module {name}"))
+    }
+
+    fn module(&self, name: &str) -> Option<&Module> {
+        self.modules.get(name).and_then(Option::as_ref)
+    }
+
+    /// Names of the modules sending pulses to `name`
+    pub fn inputs_of(&self, name: &str) -> Vec<&str> {
+        self.modules.id(name).map_or(vec![], |id| {
+            self.inputs[id.index()]
                 .iter()
-                .filter(|(_, m)| m.destinations.contains(&module.name))
-                .map(|(name, _)| name.clone())
-                .collect();
-            (module.name.clone(), froms)
+                .map(|input| self.name(*input))
+                .collect()
         })
-        .collect();
+    }
 
-    let mut modules: HashMap<String, Module> = modules
-        .into_iter()
-        .map(|(name, mut module)| {
-            if let ModuleType::Conjunction(ref mut states) = module.module_type {
-                // states.insert(name.clone(), Pulse::Low);
-                connections.get(&name).unwrap().iter().for_each(|d| {
-                    states.insert(d.clone(), Pulse::Low);
-                });
-            }
-            (name, module)
-        })
-        .collect();
+    pub fn is_conjunction(&self, name: &str) -> bool {
+        self.module(name)
+            .is_some_and(|m| matches!(m.module_type, ModuleType::Conjunction { .. }))
+    }
 
-    let mut queue: VecDeque<(String, String, Pulse)> = VecDeque::new();
-    let mut lows = 0;
-    let mut highs = 0;
-    for _ in 0..1000 {
-        let mut first_pulse = VecDeque::new();
-        first_pulse.push_back(("button".to_string(), "broadcast".to_string(), Pulse::Low));
-        queue.append(&mut first_pulse);
-        while let Some((from, to, pulse)) = queue.pop_front() {
-            match pulse {
-                Pulse::High => highs += 1,
-                Pulse::Low => lows += 1,
+    /// Starts recording the presses on which `pulse` is sent from `from` to `to`
+    pub fn watch(&mut self, from: &str, to: &str, pulse: Pulse) -> Result<WatchId> {
+        self.watches.push(EdgeWatch {
+            from: self.id(from)?,
+            to: self.id(to)?,
+            pulse,
+            hits: vec![],
+        });
+        Ok(self.watches.len() - 1)
+    }
+
+    pub fn watch_hits(&self, id: WatchId) -> &[usize] {
+        &self.watches[id].hits
+    }
+
+    /// Presses the button once and returns every pulse sent, in order
+    pub fn press_button(&mut self) -> Result<Vec<PulseEvent>> {
+        let mut trace = vec![];
+        self.press_button_with(|event| trace.push(*event))?;
+        Ok(trace)
+    }
+
+    /// Presses the button once, calling `on_pulse` for every pulse sent
+    pub fn press_button_with(&mut self, mut on_pulse: impl FnMut(&PulseEvent)) -> Result<()> {
+        self.presses += 1;
+        let start = PulseEvent {
+            from: self.button,
+            to: self.broadcaster,
+            pulse: Pulse::Low,
+        };
+        let mut queue = VecDeque::from([(start, 0)]);
+        while let Some((event, slot)) = queue.pop_front() {
+            on_pulse(&event);
+            for watch in self.watches.iter_mut() {
+                if watch.pulse == event.pulse
+                    && watch.from == event.from
+                    && watch.to == event.to
+                    && watch.hits.last() != Some(&self.presses)
+                {
+                    watch.hits.push(self.presses);
+                }
             }
-            if let Some(module) = modules.get_mut(&to) {
-                let mut new_pulses = module.process_pulse(pulse, &from);
-                queue.append(&mut new_pulses);
+            let Some(module) = &mut self.modules[event.to] else {
+                continue;
+            };
+            if let Some(pulse) = module.process_pulse(event.pulse, slot) {
+                queue.extend(module.destinations.iter().map(|edge| {
+                    let next = PulseEvent {
+                        from: event.to,
+                        to: edge.to,
+                        pulse,
+                    };
+                    (next, edge.slot)
+                }));
             }
         }
+        Ok(())
     }
 
-    eprintln!("{:?}", Instant::now() - start_time);
-    Ok(lows * highs)
+    /// For a `target` fed by a single conjunction, records on which presses
+    /// each input of that conjunction sends it a high pulse and checks that
+    /// they do so periodically. The conjunction sends a low pulse to `target`
+    /// once all inputs are high on the same press. The cycles are sorted by
+    /// input name.
+    pub fn analyse_feeder_cycles(
+        &mut self,
+        target: &str,
+        max_presses: usize,
+    ) -> Result<Vec<FeederCycle>> {
+        let [feeder] = self.inputs_of(target)[..] else {
+            return Err(anyhow!("{target} is not fed by exactly one module"));
+        };
+        let feeder = feeder.to_string();
+        if !self.is_conjunction(&feeder) {
+            return Err(anyhow!("{feeder} feeding {target} is not a conjunction"));
+        }
+        let mut inputs: Vec<String> = self
+            .inputs_of(&feeder)
+            .into_iter()
+            .map(String::from)
+            .collect();
+        inputs.sort_unstable();
+        let watches: Vec<(String, WatchId)> = inputs
+            .into_iter()
+            .map(|input| {
+                let id = self.watch(&input, &feeder, Pulse::High)?;
+                Ok((input, id))
+            })
+            .collect::<Result<_>>()?;
+        while watches
+            .iter()
+            .any(|(_, id)| self.watch_hits(*id).len() < FeederCycle::REQUIRED_HITS)
+        {
+            if self.presses >= max_presses {
+                let (input, _) = watches
+                    .iter()
+                    .find(|(_, id)| self.watch_hits(*id).len() < FeederCycle::REQUIRED_HITS)
+                    .expect("loop condition guarantees a missing input");
+                return Err(anyhow!(
+                    "{input} sent fewer than {} high pulses to {feeder} in {max_presses} presses",
+                    FeederCycle::REQUIRED_HITS
+                ));
+            }
+            self.press_button_with(|_| {})?;
+        }
+        watches
+            .into_iter()
+            .map(|(input, id)| FeederCycle::from_hits(input, self.watch_hits(id)))
+            .collect()
+    }
 }
 
-pub fn solve_task_two(#[allow(unused_variables)] input: Vec<String>) -> Result<i64> {
-    let start_time = Instant::now();
-    let modules = input
-        .iter()
-        .map(|s| Module::from_str(s))
-        .map(|m| m.map(|m| (m.name.clone(), m)))
-        .collect::<Result<HashMap<_, _>>>()?;
-    let connections: HashMap<String, VecDeque<String>> = modules
-        .values()
-        .filter(|m| matches!(m.module_type, ModuleType::Conjunction(_)))
-        // .map(|m| (m, VecDeque::new()))
-        .map(|module| {
-            let froms = modules
-                .iter()
-                .filter(|(_, m)| m.destinations.contains(&module.name))
-                .map(|(name, _)| name.clone())
-                .collect();
-            (module.name.clone(), froms)
-        })
-        .collect();
+/// Presses on which an input of the feeder conjunction sends a high pulse:
+/// `offset`, `offset + period`, `offset + 2 * period`, ...
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeederCycle {
+    pub input: String,
+    pub offset: usize,
+    pub period: usize,
+}
 
-    let mut modules: HashMap<String, Module> = modules
-        .into_iter()
-        .map(|(name, mut module)| {
-            if let ModuleType::Conjunction(ref mut states) = module.module_type {
-                // states.insert(name.clone(), Pulse::Low);
-                connections.get(&name).unwrap().iter().for_each(|d| {
-                    states.insert(d.clone(), Pulse::Low);
-                });
-            }
-            (name, module)
+impl FeederCycle {
+    /// Hits needed to see the period repeat at least once
+    const REQUIRED_HITS: usize = 3;
+
+    fn from_hits(input: String, hits: &[usize]) -> Result<Self> {
+        let [first, second, ..] = hits else {
+            return Err(anyhow!("{input} has too few high pulses to find a cycle"));
+        };
+        let period = second - first;
+        if period == 0 {
+            return Err(anyhow!(
+                "{input} has several high pulses on press {first}, hits have to be distinct"
+            ));
+        }
+        if let Some(window) = hits.windows(2).find(|w| w[1] - w[0] != period) {
+            return Err(anyhow!(
+                "{input} is not periodic: high pulses on presses {hits:?}, expected a gap of {period} between {} and {}",
+                window[0],
+                window[1]
+            ));
+        }
+        Ok(Self {
+            input,
+            offset: *first,
+            period,
         })
-        .collect();
+    }
 
-    let feeds = modules
-        .iter()
-        .filter(|(_, m)| m.destinations.contains(&"rx".to_string()))
-        .map(|(name, _)| name.clone())
-        .next()
-        .unwrap();
-
-    let mut cycle_lengths: HashMap<String, Option<usize>> = connections
-        .get(&feeds)
-        .unwrap()
-        .iter()
-        .map(|q| (q.clone(), None))
-        .collect();
+    /// Whether the cycle starts at press 0, making the least common multiple
+    /// of the periods the first press all inputs are high together
+    pub fn starts_at_zero(&self) -> bool {
+        self.offset == self.period
+    }
+}
 
-    let mut seen: HashMap<String, usize> = connections
-        .get(&feeds)
-        .unwrap()
+/// First press on which every input of the feeder conjunction sends a high pulse
+pub fn first_common_press(cycles: &[FeederCycle]) -> Result<usize> {
+    if cycles.iter().all(|c| c.starts_at_zero()) {
+        let press = cycles.iter().fold(1, |acc, c| lcm(acc, c.period as i128));
+        return Ok(usize::try_from(press)?);
+    }
+    // The cycles have offsets, fall back to solving the congruences
+    let congruences: Vec<(i128, i128)> = cycles
         .iter()
-        .map(|q| (q.clone(), 0))
+        .map(|c| (c.offset as i128, c.period as i128))
         .collect();
+    let (press, modulus) = crt(&congruences).ok_or_else(|| {
+        anyhow!("The inputs never send high pulses on the same press: {cycles:?}")
+    })?;
+    let latest_offset = cycles.iter().map(|c| c.offset as i128).max().unwrap_or(0);
+    let press = if press < latest_offset {
+        press + (latest_offset - press + modulus - 1) / modulus * modulus
+    } else {
+        press
+    };
+    Ok(usize::try_from(press)?)
+}
 
-    let mut queue: VecDeque<(String, String, Pulse)> = VecDeque::new();
-    let mut presses = 0;
-    let sol = 'outer: loop {
-        presses += 1;
-        let mut first_pulse = VecDeque::new();
-        first_pulse.push_back(("button".to_string(), "broadcast".to_string(), Pulse::Low));
-        queue.append(&mut first_pulse);
-        while let Some((from, to, pulse)) = queue.pop_front() {
-            if let Some(module) = modules.get_mut(&to) {
-                let mut new_pulses = module.process_pulse(pulse, &from);
-                queue.append(&mut new_pulses);
-            }
-            if to == feeds && pulse == Pulse::High {
-                seen.insert(from.clone(), seen.get(&from).unwrap() + 1);
-                if let Some(cycle_length) = cycle_lengths.get(&from).unwrap() {
-                    if !presses == seen[&from] * cycle_length {
-                        panic!("Not a cycle");
+/// Which part of the network a module belongs to when exported as a graph
+struct GraphLayout {
+    names: Vec<String>,
+    /// Modules grouped by the input of the feeder conjunction they drive
+    feeder_groups: Vec<(String, Vec<String>)>,
+}
+
+impl Network {
+    /// All module names, including destinations without a definition, sorted
+    fn graph_layout(&self, target: &str) -> GraphLayout {
+        let mut names: Vec<String> = self
+            .modules
+            .iter()
+            .filter(|(id, module)| module.is_some() || !self.inputs[id.index()].is_empty())
+            .map(|(id, _)| self.name(id).to_string())
+            .collect();
+        names.sort();
+
+        let mut feeder_groups = vec![];
+        if let [feeder] = self.inputs_of(target)[..] {
+            if self.is_conjunction(feeder) {
+                for input in self.inputs_of(feeder) {
+                    let mut group = vec![input.to_string()];
+                    let mut queue = VecDeque::from([input]);
+                    while let Some(name) = queue.pop_front() {
+                        for source in self.inputs_of(name) {
+                            if source != Self::BROADCASTER
+                                && source != feeder
+                                && !group.iter().any(|g| g == source)
+                            {
+                                group.push(source.to_string());
+                                queue.push_back(source);
+                            }
+                        }
                     }
-                } else {
-                    cycle_lengths.insert(from.clone(), Some(presses));
+                    group.sort();
+                    feeder_groups.push((input.to_string(), group));
                 }
+            }
+        }
+        // Modules shared between several groups are drawn outside of them
+        let all_grouped: Vec<&String> = feeder_groups.iter().flat_map(|(_, g)| g).collect();
+        let shared: Vec<String> = all_grouped
+            .iter()
+            .filter(|name| all_grouped.iter().filter(|n| n == name).count() > 1)
+            .map(|name| (*name).clone())
+            .collect();
+        for (_, group) in feeder_groups.iter_mut() {
+            group.retain(|name| !shared.contains(name));
+        }
+        feeder_groups.retain(|(_, group)| !group.is_empty());
+        feeder_groups.sort();
+        GraphLayout {
+            names,
+            feeder_groups,
+        }
+    }
 
-                if seen.iter().all(|(_, v)| *v > 1) {
-                    break 'outer cycle_lengths;
+    /// Exports the module graph in Graphviz DOT format. The modules driving
+    /// each input of the conjunction feeding `target` are drawn as clusters.
+    pub fn to_dot(&self, target: &str) -> String {
+        let layout = self.graph_layout(target);
+        let mut dot = String::from("digraph network {\n    rankdir=LR;\n");
+        for name in &layout.names {
+            let attributes = match self.module(name).map(|m| &m.module_type) {
+                Some(ModuleType::Broadcast) => "shape=doublecircle",
+                Some(ModuleType::FlipFlop(_)) => "shape=box, style=filled, fillcolor=lightblue",
+                Some(ModuleType::Conjunction { .. }) => {
+                    "shape=diamond, style=filled, fillcolor=lightsalmon"
                 }
+                None if name == target => "shape=doubleoctagon, style=filled, fillcolor=gold",
+                None => "shape=octagon",
+            };
+            dot.push_str(&format!("    {name} [{attributes}];\n"));
+        }
+        for (input, group) in &layout.feeder_groups {
+            dot.push_str(&format!(
+                "    subgraph cluster_{input} {{\n        label=\"{input}\";\n        color=red;\n"
+            ));
+            for name in group {
+                dot.push_str(&format!("        {name};\n"));
             }
+            dot.push_str("    }\n");
         }
-    };
+        for name in &layout.names {
+            let Some(module) = self.module(name) else {
+                continue;
+            };
+            for edge in &module.destinations {
+                let destination = self.name(edge.to);
+                dot.push_str(&format!("    {name} -> {destination};\n"));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Exports the module graph as a Mermaid flowchart, grouped like [`Network::to_dot`]
+    pub fn to_mermaid(&self, target: &str) -> String {
+        let layout = self.graph_layout(target);
+        let mut mermaid = String::from("flowchart LR\n");
+        for name in &layout.names {
+            let node = match self.module(name).map(|m| &m.module_type) {
+                Some(ModuleType::Broadcast) => format!("{name}(({name}))"),
+                Some(ModuleType::FlipFlop(_)) => format!("{name}[{name}]"),
+                Some(ModuleType::Conjunction { .. }) => format!("{name}{{{name}}}"),
+                None => format!("{name}([{name}])"),
+            };
+            mermaid.push_str(&format!("    {node}\n"));
+        }
+        for (input, group) in &layout.feeder_groups {
+            mermaid.push_str(&format!("    subgraph cluster_{input} [{input}]\n"));
+            for name in group {
+                mermaid.push_str(&format!("        {name}\n"));
+            }
+            mermaid.push_str("    end\n");
+            mermaid.push_str(&format!("    style cluster_{input} stroke:red\n"));
+        }
+        for name in &layout.names {
+            let Some(module) = self.module(name) else {
+                continue;
+            };
+            for edge in &module.destinations {
+                let destination = self.name(edge.to);
+                mermaid.push_str(&format!("    {name} --> {destination}\n"));
+            }
+        }
+        let of_kind = |kind: fn(&ModuleType) -> bool| {
+            layout
+                .names
+                .iter()
+                .filter(|name| self.module(name).is_some_and(|m| kind(&m.module_type)))
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        mermaid.push_str("    classDef flipflop fill:#add8e6\n");
+        mermaid.push_str("    classDef conjunction fill:#ffa07a\n");
+        mermaid.push_str("    classDef target fill:#ffd700\n");
+        let flip_flops = of_kind(|t| matches!(t, ModuleType::FlipFlop(_)));
+        if !flip_flops.is_empty() {
+            mermaid.push_str(&format!("    class {flip_flops} flipflop\n"));
+        }
+        let conjunctions = of_kind(|t| matches!(t, ModuleType::Conjunction { .. }));
+        if !conjunctions.is_empty() {
+            mermaid.push_str(&format!("    class {conjunctions} conjunction\n"));
+        }
+        if layout.names.iter().any(|n| n == target) {
+            mermaid.push_str(&format!("    class {target} target\n"));
+        }
+        mermaid
+    }
+}
+
+pub fn solve_task_one(input: Vec<String>) -> Result<Answer> {
+    let start_time = Instant::now();
+    let mut network = Network::from_lines(&input)?;
+
+    let mut lows: u64 = 0;
+    let mut highs: u64 = 0;
+    for _ in 0..1000 {
+        network.press_button_with(|event| match event.pulse {
+            Pulse::High => highs += 1,
+            Pulse::Low => lows += 1,
+        })?;
+    }
+
+    eprintln!("{:?}", Instant::now() - start_time);
+    Ok(lows.try_mul(highs)?.into())
+}
+
+pub fn solve_task_two(input: Vec<String>) -> Result<Answer> {
+    let start_time = Instant::now();
+    let mut network = Network::from_lines(&input)?;
+
+    let cycles = network.analyse_feeder_cycles("rx", 100_000)?;
+    eprintln!("{:?}", cycles);
+    let sol = first_common_press(&cycles)?;
 
-    eprintln!("{:?}", sol);
     eprintln!("{:?}", Instant::now() - start_time);
-    Ok(sol
-        .values()
-        .map(|n| n.unwrap())
-        .fold(1, |acc, b| acc.lcm(&b)) as i64)
+    Ok(sol.into())
 }
 
 #[cfg(test)]
@@ -277,7 +624,7 @@ mod test {
         Ok(lines?)
     }
 
-    use crate::{solve_task_one, solve_task_two};
+    use crate::{first_common_press, solve_task_one, solve_task_two, FeederCycle, Network, Pulse};
     #[test]
     fn test_case_one_example_1() -> Result<()> {
         let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -310,12 +657,162 @@ mod test {
         assert_eq!(solve_task_two(file)?, 0);
         Ok(())
     }
-    #[ignore = "Not implemented yet"]
     #[test]
     fn test_case_two_solve() -> Result<()> {
         let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         let file = get_file(cargo_manifest_dir.join("inputs/full.txt"))?;
-        assert_eq!(solve_task_two(file)?, 0);
+        assert_eq!(solve_task_two(file)?, 247454898168563);
+        Ok(())
+    }
+
+    #[test]
+    fn test_press_button_trace() -> Result<()> {
+        let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let mut network =
+            Network::from_lines(&get_file(cargo_manifest_dir.join("inputs/example_1.txt"))?)?;
+        let trace = get_file(cargo_manifest_dir.join("inputs/example_2.txt"))?;
+        let pulses: Vec<String> = network
+            .press_button()?
+            .iter()
+            .map(|e| network.describe(e))
+            .collect();
+        assert_eq!(pulses, trace);
+
+        let mut network =
+            Network::from_lines(&get_file(cargo_manifest_dir.join("inputs/example_3.txt"))?)?;
+        for example in 4..=7 {
+            let trace = get_file(cargo_manifest_dir.join(format!("inputs/example_{example}.txt")))?;
+            let pulses: Vec<String> = network
+                .press_button()?
+                .iter()
+                .map(|e| network.describe(e))
+                .collect();
+            assert_eq!(pulses, trace);
+        }
+        assert_eq!(network.presses(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_watch_edge() -> Result<()> {
+        let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let mut network =
+            Network::from_lines(&get_file(cargo_manifest_dir.join("inputs/example_3.txt"))?)?;
+        let id = network.watch("con", "output", Pulse::Low)?;
+        assert!(network.watch("con", "nowhere", Pulse::Low).is_err());
+        for _ in 0..4 {
+            network.press_button()?;
+        }
+        assert_eq!(network.watch_hits(id), &[1, 3]);
+        assert!(network.analyse_feeder_cycles("rx", 100).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_graph_export() -> Result<()> {
+        let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let network =
+            Network::from_lines(&get_file(cargo_manifest_dir.join("inputs/example_3.txt"))?)?;
+        let dot = network.to_dot("output");
+        assert!(dot.starts_with("digraph network {"));
+        assert!(dot.contains("    con [shape=diamond, style=filled, fillcolor=lightsalmon];"));
+        assert!(dot.contains("    output [shape=doubleoctagon, style=filled, fillcolor=gold];"));
+        assert!(dot.contains("    subgraph cluster_b {"));
+        assert!(dot.contains("    a -> inv;"));
+
+        let mermaid = network.to_mermaid("output");
+        assert!(mermaid.starts_with("flowchart LR"));
+        assert!(mermaid.contains("    inv{inv}"));
+        assert!(mermaid.contains("    subgraph cluster_b [b]"));
+        assert!(mermaid.contains("    style cluster_b stroke:red"));
+        assert!(!mermaid.contains("cluster_a"));
+        assert!(mermaid.contains("    a --> con"));
+        assert!(mermaid.contains("    class a,b flipflop"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_feeder_cycles() -> Result<()> {
+        let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let mut network =
+            Network::from_lines(&get_file(cargo_manifest_dir.join("inputs/full.txt"))?)?;
+        let cycles = network.analyse_feeder_cycles("rx", 100_000)?;
+        assert!(cycles.iter().all(|c| c.starts_at_zero()));
+        assert_eq!(first_common_press(&cycles)?, 247454898168563);
+        Ok(())
+    }
+
+    #[test]
+    fn test_feeder_cycles_with_offsets() -> Result<()> {
+        // a is high on presses 1, 3, 5, ... and b on 2, 6, 10, ...
+        let lines = [
+            "broadcaster -> a",
+            "%a -> b, con",
+            "%b -> con",
+            "&con -> rx",
+        ]
+        .map(String::from);
+        let mut network = Network::from_lines(&lines)?;
+        let cycles = network.analyse_feeder_cycles("rx", 100)?;
+        assert_eq!(
+            cycles,
+            vec![
+                FeederCycle {
+                    input: "a".to_string(),
+                    offset: 1,
+                    period: 2
+                },
+                FeederCycle {
+                    input: "b".to_string(),
+                    offset: 2,
+                    period: 4
+                }
+            ]
+        );
+        assert!(first_common_press(&cycles).is_err());
+
+        let offset_cycles = [
+            FeederCycle {
+                input: "a".to_string(),
+                offset: 2,
+                period: 3,
+            },
+            FeederCycle {
+                input: "b".to_string(),
+                offset: 3,
+                period: 5,
+            },
+        ];
+        assert_eq!(first_common_press(&offset_cycles)?, 8);
+        Ok(())
+    }
+
+    #[test]
+    fn test_feeder_cycles_several_pulses_per_press() -> Result<()> {
+        // x sends con three high pulses on every press
+        let lines = ["broadcaster -> x, x, x", "&x -> con", "&con -> rx"].map(String::from);
+        let mut network = Network::from_lines(&lines)?;
+        let cycles = network.analyse_feeder_cycles("rx", 100)?;
+        assert_eq!(
+            cycles,
+            vec![FeederCycle {
+                input: "x".to_string(),
+                offset: 1,
+                period: 1
+            }]
+        );
+        assert_eq!(first_common_press(&cycles)?, 1);
+
+        assert!(FeederCycle::from_hits("x".to_string(), &[1, 1, 1]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_feeder_never_high() -> Result<()> {
+        let lines = ["broadcaster -> con", "&con -> rx"].map(String::from);
+        let mut network = Network::from_lines(&lines)?;
+        let err = network.analyse_feeder_cycles("rx", 100).unwrap_err();
+        assert!(err.to_string().contains("broadcaster"));
         Ok(())
     }
 }
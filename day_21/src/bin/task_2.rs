@@ -1,14 +1,22 @@
-use std::path::PathBuf;
-
+use clap::Parser;
 use color_eyre::Result;
 
-use common::get_file;
+use common::CommonCli;
+
+#[derive(Parser)]
+struct Args {
+    #[command(flatten)]
+    input: CommonCli,
+    /// Number of steps the elf takes
+    #[arg(short, long, default_value_t = 26501365)]
+    steps: i32,
+}
 
 fn main() -> Result<()> {
     color_eyre::install()?;
-    let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-    let file = get_file(cargo_manifest_dir.join("inputs/full.txt"))?;
-    let sol = solution::solve_task_two(file)?;
+    let args = Args::parse();
+    let file = args.input.read_input(env!("CARGO_MANIFEST_DIR"))?;
+    let sol = solution::solve_task_two(file, args.steps)?;
     println!("{sol}");
     Ok(())
 }
@@ -3,7 +3,8 @@ use std::{
     time::Instant,
 };
 
-use color_eyre::Result;
+use color_eyre::{eyre::anyhow, Result};
+use common::{answer::Answer, sequence::Polynomial};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum MapTile {
@@ -79,7 +80,7 @@ impl Map {
     }
 }
 
-pub fn solve_task_one(input: Vec<String>, steps: i32) -> Result<i32> {
+pub fn solve_task_one(input: Vec<String>, steps: i32) -> Result<Answer> {
     let start_time = Instant::now();
     let mut map = Map::from_lines(input);
     let mut queue = map
@@ -111,7 +112,7 @@ pub fn solve_task_one(input: Vec<String>, steps: i32) -> Result<i32> {
     }
 
     eprintln!("{:?}", Instant::now() - start_time);
-    Ok(visited.len() as i32)
+    Ok(visited.len().into())
 }
 
 struct InfiniteMap {
@@ -123,13 +124,12 @@ impl InfiniteMap {
     fn from_map(map: Map) -> Self {
         let base_tiles = map.tiles;
         let start = (map.start.0 as i32, map.start.1 as i32);
-        let new_map = Self { base_tiles, start };
-        new_map
+        Self { base_tiles, start }
     }
 
     fn get_tile(&self, x: i32, y: i32) -> MapTile {
-        let x = (x % self.base_tiles[0].len() as i32) as usize;
-        let y = (y % self.base_tiles.len() as i32) as usize;
+        let x = x.rem_euclid(self.base_tiles[0].len() as i32) as usize;
+        let y = y.rem_euclid(self.base_tiles.len() as i32) as usize;
         self.base_tiles[y][x]
     }
     fn get_neighbors(&self, x: i32, y: i32) -> VecDeque<(i32, i32)> {
@@ -151,33 +151,101 @@ impl InfiniteMap {
     }
 }
 
-pub fn solve_task_two(input: Vec<String>, steps: i32) -> Result<i32> {
+impl InfiniteMap {
+    fn width(&self) -> usize {
+        self.base_tiles[0].len()
+    }
+
+    fn height(&self) -> usize {
+        self.base_tiles.len()
+    }
+
+    /// Fewest steps from the start to every cell of a block of
+    /// `2 * radius + 1` by `2 * radius + 1` copies of the map around the start
+    fn distances(&self, radius: usize) -> Vec<Option<u32>> {
+        let (width, height) = (self.width(), self.height());
+        let block_width = (2 * radius + 1) * width;
+        let block_height = (2 * radius + 1) * height;
+        let offset_x = (radius * width) as i32;
+        let offset_y = (radius * height) as i32;
+        let index =
+            |x: i32, y: i32| (y + offset_y) as usize * block_width + (x + offset_x) as usize;
+        let in_block = |x: i32, y: i32| {
+            (0..block_width as i32).contains(&(x + offset_x))
+                && (0..block_height as i32).contains(&(y + offset_y))
+        };
+
+        let mut distances = vec![None; block_width * block_height];
+        distances[index(self.start.0, self.start.1)] = Some(0);
+        let mut queue = VecDeque::from([(self.start.0, self.start.1, 0)]);
+        while let Some((x, y, steps)) = queue.pop_front() {
+            for (x, y) in self.get_neighbors(x, y) {
+                if in_block(x, y) && distances[index(x, y)].is_none() {
+                    distances[index(x, y)] = Some(steps + 1);
+                    queue.push_back((x, y, steps + 1));
+                }
+            }
+        }
+        distances
+    }
+}
+
+/// Cells the elf can end on after exactly `steps` steps: those it reaches in
+/// at most that many steps with the same parity, as it can step back and forth
+fn count_reachable(distances: &[Option<u32>], steps: u32) -> usize {
+    distances
+        .iter()
+        .flatten()
+        .filter(|&&d| d <= steps && d % 2 == steps % 2)
+        .count()
+}
+
+/// Most periods to wait for the reachable area to grow quadratically
+const MAX_SKIPPED_PERIODS: u32 = 16;
+
+pub fn solve_task_two(input: Vec<String>, steps: i32) -> Result<Answer> {
     let start_time = Instant::now();
     let map = Map::from_lines(input);
     let infinite_map = InfiniteMap::from_map(map);
-    let start = infinite_map.start;
-    let mut queue = VecDeque::new();
-    queue.push_back((start.0, start.1, 0));
-    let mut visited = HashSet::new();
-    while let Some((x, y, step)) = queue.pop_front() {
-        if step > steps {
-            break;
+    let steps = u32::try_from(steps)?;
+    let shortest_side = infinite_map.width().min(infinite_map.height());
+    let period = infinite_map.width() as u32;
+    let (remainder, periods) = (steps % period, steps / period);
+
+    // The count after `remainder + n * period` steps becomes quadratic in `n`
+    // once the reachable area spans a few copies of the map. Fit it through
+    // five samples, so the fit also checks the degree, starting as early as
+    // the samples allow.
+    let mut radius = 0;
+    let mut distances = vec![];
+    for skipped in 0..=MAX_SKIPPED_PERIODS {
+        if periods < skipped + 5 {
+            let distances = infinite_map.distances(steps as usize / shortest_side + 1);
+            let sol = count_reachable(&distances, steps);
+            eprintln!("{:?}", Instant::now() - start_time);
+            return Ok(sol.into());
         }
-        if visited.contains(&(x, y)) {
-            continue;
+        if infinite_map.width() != infinite_map.height() {
+            return Err(anyhow!(
+                "Can't extrapolate {steps} steps on a map that isn't square"
+            ));
         }
-        if step % 2 == 0 {
-            visited.insert((x, y));
+        if radius < skipped as usize + 6 {
+            radius = skipped as usize + 6;
+            distances = infinite_map.distances(radius);
+        }
+        let samples: Vec<i64> = (skipped..skipped + 5)
+            .map(|n| count_reachable(&distances, remainder + n * period) as i64)
+            .collect();
+        if let Ok(polynomial) = Polynomial::fit(&samples, 2) {
+            let sol = polynomial.eval((periods - skipped) as i64)?;
+            eprintln!("{:?}", Instant::now() - start_time);
+            return Ok(sol.into());
         }
-        queue.extend(
-            infinite_map
-                .get_neighbors(x, y)
-                .into_iter()
-                .map(|(x, y)| (x, y, step + 1)),
-        );
     }
-    eprintln!("{:?}", Instant::now() - start_time);
-    todo!()
+    Err(anyhow!(
+        "The reachable area doesn't grow quadratically within {MAX_SKIPPED_PERIODS} periods"
+    ))
 }
 
 #[cfg(test)]
@@ -220,16 +288,35 @@ mod test {
         let file = get_file(cargo_manifest_dir.join("inputs/example_1.txt"))?;
         let sols = [(6, 16), (10, 50), (50, 1594), (100, 6536)];
         for (steps, sol) in sols {
-            assert_eq!(solve_task_one(file.clone(), steps)?, sol);
+            assert_eq!(solve_task_two(file.clone(), steps)?, sol);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_case_two_extrapolated_example() -> Result<()> {
+        let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let file = get_file(cargo_manifest_dir.join("inputs/example_1.txt"))?;
+        let sols = [(500, 167004), (1000, 668697), (5000, 16733044)];
+        for (steps, sol) in sols {
+            assert_eq!(solve_task_two(file.clone(), steps)?, sol);
         }
         Ok(())
     }
-    #[ignore = "Not implemented yet"]
+
+    #[test]
+    fn test_case_two_not_square() -> Result<()> {
+        let file = vec!["...".to_string(), ".S.".to_string()];
+        assert_eq!(solve_task_two(file.clone(), 3)?, 16);
+        assert!(solve_task_two(file, 1000).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_case_two_solve() -> Result<()> {
         let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         let file = get_file(cargo_manifest_dir.join("inputs/full.txt"))?;
-        assert_eq!(solve_task_two(file, 26501365)?, 0);
+        assert_eq!(solve_task_two(file, 26501365)?, 622926941971282);
         Ok(())
     }
 }
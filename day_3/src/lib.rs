@@ -1,278 +1,199 @@
-use std::{slice::Iter, time::Instant};
+use std::time::Instant;
 
 use color_eyre::{
     eyre::{anyhow, Error},
     Result,
 };
-#[derive(Debug, Clone, Copy)]
-enum Symbol {
-    Star,
-    Hash,
-    Plus,
-    Dollar,
-    Slash,
-    Ampersand,
-    Percent,
-    At,
-    Equals,
-    Dash,
-}
-
-impl From<Symbol> for char {
-    fn from(value: Symbol) -> char {
-        match value {
-            Symbol::Star => '*',
-            Symbol::Hash => '#',
-            Symbol::Plus => '+',
-            Symbol::Dollar => '$',
-            Symbol::Slash => '/',
-            Symbol::Ampersand => '&',
-            Symbol::Percent => '%',
-            Symbol::At => '@',
-            Symbol::Equals => '=',
-            Symbol::Dash => '-',
-        }
-    }
-}
+use common::answer::{Answer, CheckedIterator};
 
-impl TryFrom<char> for Symbol {
-    type Error = Error;
-    fn try_from(value: char) -> std::prelude::v1::Result<Self, Self::Error> {
-        match value {
-            '*' => Ok(Symbol::Star),
-            '#' => Ok(Symbol::Hash),
-            '+' => Ok(Symbol::Plus),
-            '$' => Ok(Symbol::Dollar),
-            '/' => Ok(Symbol::Slash),
-            '&' => Ok(Symbol::Ampersand),
-            '%' => Ok(Symbol::Percent),
-            '@' => Ok(Symbol::At),
-            '=' => Ok(Symbol::Equals),
-            '-' => Ok(Symbol::Dash),
-            _ => Err(anyhow!("Symbol not known {}", value)),
-        }
-    }
-}
-
-impl Symbol {
-    pub fn iterator() -> Iter<'static, Self> {
-        static SYMBOLS: [Symbol; 10] = [
-            Symbol::Ampersand,
-            Symbol::At,
-            Symbol::Dollar,
-            Symbol::Hash,
-            Symbol::Percent,
-            Symbol::Plus,
-            Symbol::Slash,
-            Symbol::Star,
-            Symbol::Equals,
-            Symbol::Dash,
-        ];
-        SYMBOLS.iter()
-    }
-}
-#[derive(Debug)]
-struct NumberLocation {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartNumber {
+    pub value: i32,
     pub row_idx: usize,
     /// id of the first char
     pub start: usize,
     /// id of the last char (not the one after that)
     pub end: usize,
 }
-#[derive(Debug, Copy, Clone)]
-struct SymolLocation {
+
+/// Any character that is neither a digit nor `.`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Symbol {
+    pub char: char,
     pub row_idx: usize,
     pub col_idx: usize,
 }
-#[derive(Debug)]
-enum SchematicPoi {
-    Number(i32, NumberLocation),
-    Symbol(Symbol, SymolLocation),
+
+/// What occupies a cell, as an index into the numbers or symbols
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Empty,
+    Number(usize),
+    Symbol(usize),
 }
 
-struct SchematicParser {
-    pub pois: Vec<SchematicPoi>,
-    current_num: Option<String>,
-    current_row: usize,
-    current_col: usize,
+/// An engine schematic indexed by cell, so every adjacency query only looks
+/// at the cells bordering what it starts from.
+#[derive(Debug)]
+pub struct Schematic {
+    pub numbers: Vec<PartNumber>,
+    pub symbols: Vec<Symbol>,
+    cells: Vec<Vec<Cell>>,
 }
 
-impl SchematicParser {
-    pub fn new() -> Self {
-        Self {
-            pois: vec![],
-            current_col: 0,
-            current_row: 0,
-            current_num: None,
+impl TryFrom<Vec<String>> for Schematic {
+    type Error = Error;
+
+    fn try_from(value: Vec<String>) -> Result<Self, Self::Error> {
+        let mut numbers = vec![];
+        let mut symbols = vec![];
+        let mut cells = vec![];
+        for (row_idx, line) in value.iter().enumerate() {
+            let mut row = vec![Cell::Empty; line.chars().count()];
+            let mut current_num: Option<(usize, String)> = None;
+            // A trailing '.' ends a number on the last column like any other
+            for (col_idx, char) in line.chars().chain(['.']).enumerate() {
+                if char.is_ascii_digit() {
+                    current_num
+                        .get_or_insert((col_idx, String::new()))
+                        .1
+                        .push(char);
+                    continue;
+                }
+                if let Some((start, digits)) = current_num.take() {
+                    let number = PartNumber {
+                        value: digits
+                            .parse()
+                            .map_err(|e| anyhow!("Invalid number {digits}: {e}"))?,
+                        row_idx,
+                        start,
+                        end: col_idx - 1,
+                    };
+                    row[start..col_idx].fill(Cell::Number(numbers.len()));
+                    numbers.push(number);
+                }
+                if char != '.' {
+                    row[col_idx] = Cell::Symbol(symbols.len());
+                    symbols.push(Symbol {
+                        char,
+                        row_idx,
+                        col_idx,
+                    });
+                }
+            }
+            cells.push(row);
         }
+        Ok(Schematic {
+            numbers,
+            symbols,
+            cells,
+        })
     }
+}
 
-    fn store_number(&mut self, number: String) -> Result<()> {
-        self.current_num = None;
-        self.pois.push(SchematicPoi::Number(
-            number.parse()?,
-            NumberLocation {
-                row_idx: self.current_row,
-                start: self.current_col - number.len(),
-                end: self.current_col - 1,
-            },
-        ));
-        Ok(())
+impl Schematic {
+    fn cell(&self, row_idx: usize, col_idx: usize) -> Cell {
+        self.cells
+            .get(row_idx)
+            .and_then(|row| row.get(col_idx))
+            .copied()
+            .unwrap_or(Cell::Empty)
     }
 
-    fn store_symbol(&mut self, symbol: Symbol) {
-        self.pois.push(SchematicPoi::Symbol(
-            symbol,
-            SymolLocation {
-                row_idx: self.current_row,
-                col_idx: self.current_col,
-            },
-        ))
+    /// Cells bordering the columns `start..=end` of a row, plus those columns
+    /// themselves.
+    fn surrounding_cells(
+        &self,
+        row_idx: usize,
+        start: usize,
+        end: usize,
+    ) -> impl Iterator<Item = Cell> + '_ {
+        let rows = row_idx.saturating_sub(1)..=row_idx + 1;
+        rows.flat_map(move |r| (start.saturating_sub(1)..=end + 1).map(move |c| self.cell(r, c)))
     }
 
-    pub fn parse(mut self, shematic: &Vec<Vec<char>>) -> Result<Vec<SchematicPoi>> {
-        for (row, line) in shematic.iter().enumerate() {
-            self.current_row = row;
-            for (col, char) in line.iter().enumerate() {
-                self.current_col = col;
-                match char {
-                    '0'..='9' => match self.current_num.clone() {
-                        Some(num_string) => {
-                            self.current_num = Some(format!("{num_string}{char}").to_string())
-                        }
-                        None => self.current_num = Some(char.to_string()),
-                    },
-                    c if Symbol::iterator().any(|s| char::from(*s) == *c) => {
-                        match self.current_num.clone() {
-                            Some(num) => self.store_number(num)?,
-                            None => (),
-                        }
-                        self.store_symbol((*char).try_into()?)
-                    }
-                    '.' => match self.current_num.clone() {
-                        Some(num) => self.store_number(num)?,
-                        None => (),
-                    },
-                    _ => return Err(anyhow!("unknown character found: {char}")),
+    /// Indices of the numbers touching the symbol with index `symbol`,
+    /// diagonals included. Each number is listed once.
+    pub fn numbers_adjacent_to_symbol(&self, symbol: usize) -> Vec<usize> {
+        let Symbol {
+            row_idx, col_idx, ..
+        } = self.symbols[symbol];
+        let mut adjacent = vec![];
+        for cell in self.surrounding_cells(row_idx, col_idx, col_idx) {
+            if let Cell::Number(number) = cell {
+                if !adjacent.contains(&number) {
+                    adjacent.push(number);
                 }
             }
-            match self.current_num.clone() {
-                Some(num) => self.store_number(num)?,
-                None => (),
-            }
         }
-        Ok(self.pois)
+        adjacent
+    }
+
+    /// Indices of the symbols touching the number with index `number`,
+    /// diagonals included.
+    pub fn symbols_adjacent_to_number(&self, number: usize) -> Vec<usize> {
+        let PartNumber {
+            row_idx,
+            start,
+            end,
+            ..
+        } = self.numbers[number];
+        self.surrounding_cells(row_idx, start, end)
+            .filter_map(|cell| match cell {
+                Cell::Symbol(symbol) => Some(symbol),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Numbers adjacent to at least one symbol
+    pub fn part_numbers(&self) -> impl Iterator<Item = &PartNumber> + '_ {
+        (0..self.numbers.len())
+            .filter(|&number| !self.symbols_adjacent_to_number(number).is_empty())
+            .map(|number| &self.numbers[number])
+    }
+
+    /// Every `gear_char` symbol with exactly `parts` adjacent numbers,
+    /// together with those numbers.
+    pub fn gears(&self, gear_char: char, parts: usize) -> Vec<(&Symbol, Vec<&PartNumber>)> {
+        self.symbols
+            .iter()
+            .enumerate()
+            .filter(|(_, symbol)| symbol.char == gear_char)
+            .filter_map(|(idx, symbol)| {
+                let numbers = self.numbers_adjacent_to_symbol(idx);
+                (numbers.len() == parts)
+                    .then(|| (symbol, numbers.iter().map(|&n| &self.numbers[n]).collect()))
+            })
+            .collect()
     }
 }
 
-pub fn solve_task_one(input: Vec<String>) -> Result<i32> {
+pub fn solve_task_one(input: Vec<String>) -> Result<Answer> {
     let start_time = Instant::now();
-    let schematic = input
-        .into_iter()
-        .map(|l| l.chars().into_iter().collect::<Vec<char>>())
-        .collect::<Vec<_>>();
-
-    let parser = SchematicParser::new();
-    let pois = parser.parse(&schematic)?;
-    let symbols = pois
-        .iter()
-        .filter_map(|p| {
-            if let SchematicPoi::Symbol(s, loc) = p {
-                Some((*s, *loc))
-            } else {
-                None
-            }
-        })
-        .collect::<Vec<_>>();
-    let sum: i32 = pois
-        .into_iter()
-        .filter_map(|p| {
-            if let SchematicPoi::Number(num, loc) = p {
-                Some((num, loc))
-            } else {
-                None
-            }
-        })
-        .filter(|(_, num_loc)| {
-            symbols
-                .iter()
-                .any(|(_sym, SymolLocation { col_idx, row_idx })| {
-                    let in_row_range = row_idx.abs_diff(num_loc.row_idx) <= 1;
-                    let in_col_range = (num_loc.start == 0 || num_loc.start - 1 <= *col_idx)
-                        && num_loc.end + 1 >= *col_idx;
-                    in_row_range && in_col_range
-                })
-        })
-        .map(|(n, _)| n)
-        .sum();
+    let schematic = Schematic::try_from(input)?;
+    let sum = schematic.part_numbers().map(|n| n.value as i64).try_sum()?;
 
     let end_time = Instant::now();
 
     println!("Took {:#?}", end_time - start_time);
-    Ok(sum)
+    Ok(sum.into())
 }
 
-pub fn solve_task_two(input: Vec<String>) -> Result<i32> {
+pub fn solve_task_two(input: Vec<String>) -> Result<Answer> {
     let start_time = Instant::now();
-
-    let schematic = input
-        .into_iter()
-        .map(|l| l.chars().into_iter().collect::<Vec<char>>())
-        .collect::<Vec<_>>();
-
-    let parser = SchematicParser::new();
-    let pois = parser.parse(&schematic)?;
-    let gears = pois
+    let schematic = Schematic::try_from(input)?;
+    let ratios = schematic
+        .gears('*', 2)
         .iter()
-        .filter_map(|p| {
-            if let SchematicPoi::Symbol(s, loc) = p {
-                if let Symbol::Star = *s {
-                    Some((*s, *loc))
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        })
-        .collect::<Vec<_>>();
-    let numbers = pois
-        .into_iter()
-        .filter_map(|p| {
-            if let SchematicPoi::Number(num, loc) = p {
-                Some((num, loc))
-            } else {
-                None
-            }
-        })
-        .collect::<Vec<_>>();
-
-    let ratios: Vec<(i32, i32)> = gears
-        .into_iter()
-        .filter_map(|(_, gear_loc)| {
-            let ratios = numbers
-                .iter()
-                .filter(|(_, num_loc)| {
-                    let in_row_range = gear_loc.row_idx.abs_diff(num_loc.row_idx) <= 1;
-                    let in_col_range = (num_loc.start == 0
-                        || num_loc.start - 1 <= gear_loc.col_idx)
-                        && num_loc.end + 1 >= gear_loc.col_idx;
-                    in_row_range && in_col_range
-                })
-                .collect::<Vec<_>>();
-            if ratios.len() == 2 {
-                Some((ratios[0].0, ratios[1].0))
-            } else {
-                None
-            }
-        })
-        .collect();
+        .map(|(_, parts)| parts.iter().map(|n| n.value as i64).try_product())
+        .collect::<Result<Vec<_>>>()?;
+    let sol = ratios.into_iter().try_sum()?;
 
-    let sol: i32 = ratios.iter().map(|(a, b)| a * b).sum();
     let end_time = Instant::now();
 
     println!("Took {:#?}", end_time - start_time);
-    Ok(sol)
+    Ok(sol.into())
 }
 
 #[cfg(test)]
@@ -292,7 +213,7 @@ mod test {
         Ok(lines?)
     }
 
-    use crate::{solve_task_one, solve_task_two};
+    use crate::{solve_task_one, solve_task_two, Schematic};
 
     #[test]
     fn test_case_one_example() -> Result<()> {
@@ -324,4 +245,38 @@ mod test {
         assert_eq!(solve_task_two(file)?, 79026871);
         Ok(())
     }
+
+    #[test]
+    fn test_adjacency_queries() -> Result<()> {
+        let schematic = Schematic::try_from(vec![
+            "123.!..".to_string(),
+            ".?....7".to_string(),
+            "45...~8".to_string(),
+        ])?;
+        let values = |numbers: Vec<usize>| -> Vec<i32> {
+            numbers
+                .iter()
+                .map(|&n| schematic.numbers[n].value)
+                .collect()
+        };
+        assert_eq!(schematic.symbols.len(), 3);
+        // '?' touches three cells of 123 but lists it once
+        assert_eq!(values(schematic.numbers_adjacent_to_symbol(1)), [123, 45]);
+        assert_eq!(values(schematic.numbers_adjacent_to_symbol(2)), [7, 8]);
+        assert!(schematic.numbers_adjacent_to_symbol(0).is_empty());
+        assert_eq!(schematic.symbols_adjacent_to_number(0), [1]);
+        assert_eq!(schematic.symbols_adjacent_to_number(1), [2]);
+        assert_eq!(
+            schematic
+                .part_numbers()
+                .map(|n| n.value)
+                .collect::<Vec<_>>(),
+            [123, 7, 45, 8]
+        );
+        assert_eq!(schematic.gears('?', 2).len(), 1);
+        assert_eq!(schematic.gears('~', 2)[0].1[1].value, 8);
+        assert!(schematic.gears('!', 1).is_empty());
+        assert_eq!(schematic.gears('!', 0).len(), 1);
+        Ok(())
+    }
 }
@@ -1,10 +1,10 @@
 use std::{collections::HashSet, str::FromStr, time::Instant};
 
 use color_eyre::{eyre::anyhow, Result};
+use common::answer::{Answer, Checked, CheckedIterator};
 
 #[derive(Debug)]
 struct ScratchCard {
-    #[allow(dead_code)]
     id: i32,
     winning_numbers: HashSet<i32>,
     own_numbers: HashSet<i32>,
@@ -14,10 +14,14 @@ impl FromStr for ScratchCard {
     type Err = color_eyre::Report;
 
     fn from_str(s: &str) -> std::prelude::v1::Result<Self, Self::Err> {
-        let mut parts = s.split(":");
-        let id: i32 = parts.next().unwrap()[5..].trim().parse()?;
-
-        let mut parts = parts.next().unwrap().split("|");
+        let (card, numbers) = s.split_once(':').ok_or(anyhow!("Invalid card: {s}"))?;
+        let id: i32 = card
+            .strip_prefix("Card")
+            .ok_or(anyhow!("Invalid card: {s}"))?
+            .trim()
+            .parse()?;
+
+        let mut parts = numbers.split('|');
         let winning_numbers = parts
             .next()
             .ok_or(anyhow!("No winning numbers found"))?
@@ -41,13 +45,15 @@ impl FromStr for ScratchCard {
 }
 
 impl ScratchCard {
-    fn get_points(&self) -> Result<i32> {
+    fn get_points(&self) -> Result<u64> {
         let matched: u32 = self.matches();
 
         if matched == 0 {
             Ok(0)
         } else {
-            Ok(2_i32.pow(matched - 1))
+            2_u64
+                .checked_pow(matched - 1)
+                .ok_or(anyhow!("Card {} is worth too many points", self.id))
         }
     }
 
@@ -56,43 +62,36 @@ impl ScratchCard {
     }
 }
 
-pub fn solve_task_one(input: Vec<String>) -> Result<i32> {
+pub fn solve_task_one(input: Vec<String>) -> Result<Answer> {
     let start_time = Instant::now();
-    let sol = input
+    let points = input
         .iter()
-        .filter_map(|line| match line.parse::<ScratchCard>() {
-            Ok(c) => match c.get_points() {
-                Ok(n) => Some(n),
-                Err(_) => None,
-            },
-            Err(_) => {
-                panic!("Failed on line {}", line);
-            }
-        })
-        .sum();
+        .map(|line| line.parse::<ScratchCard>()?.get_points())
+        .collect::<Result<Vec<_>>>()?;
+    let sol = points.into_iter().try_sum()?;
     eprintln!("⏱️ Took: {:?}", Instant::now() - start_time);
-    Ok(sol)
+    Ok(sol.into())
 }
 
-pub fn solve_task_two(input: Vec<String>) -> Result<i32> {
+pub fn solve_task_two(input: Vec<String>) -> Result<Answer> {
     let start_time = Instant::now();
-    // HashMap<CardId, (matches)>
-    let cards = input.iter().map(|s| {
-        let c = s.parse::<ScratchCard>().unwrap();
-        c.matches()
-    });
-
-    let mut card_multiples: Vec<i32> = vec![1; cards.len()];
-
-    for (card_id, points) in cards.enumerate() {
-        for other_card_id in (card_id + 1)..(card_id + points as usize + 1) {
-            let card_multiplier = card_multiples[card_id];
-            let other_card_multiplier = card_multiples[other_card_id];
-            card_multiples[other_card_id] = card_multiplier + other_card_multiplier;
+    let matches = input
+        .iter()
+        .map(|s| Ok(s.parse::<ScratchCard>()?.matches()))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut card_multiples: Vec<u64> = vec![1; matches.len()];
+
+    for (card_id, points) in matches.into_iter().enumerate() {
+        // Cards past the end of the table aren't won
+        let last_won = (card_id + points as usize).min(card_multiples.len() - 1);
+        for other_card_id in (card_id + 1)..=last_won {
+            card_multiples[other_card_id] =
+                card_multiples[other_card_id].try_add(card_multiples[card_id])?;
         }
     }
     eprintln!("⏱️ Took: {:?}", Instant::now() - start_time);
-    Ok(card_multiples.iter().sum())
+    Ok(card_multiples.into_iter().try_sum()?.into())
 }
 
 #[cfg(test)]
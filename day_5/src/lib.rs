@@ -1,7 +1,8 @@
 use std::{ops::Range, str::FromStr, time::Instant};
 
-use color_eyre::Result;
-use rayon::prelude::*;
+use color_eyre::{eyre::anyhow, Result};
+use common::{answer::Answer, interval::IntervalSet};
+
 #[derive(Debug)]
 struct Conversion {
     dest_range_start: i64,
@@ -23,118 +24,116 @@ impl FromStr for Conversion {
     type Err = color_eyre::Report;
 
     fn from_str(s: &str) -> std::prelude::v1::Result<Self, Self::Err> {
-        let mut nums = s
+        let nums = s
             .split_ascii_whitespace()
-            .map(|e| e.parse::<i64>().unwrap());
+            .map(|e| e.parse::<i64>())
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let [dest_range_start, src_range_start, range_len] = nums[..] else {
+            return Err(anyhow!("Invalid conversion: {s}"));
+        };
         let conv = Conversion {
-            dest_range_start: nums.next().unwrap(),
-            src_range_start: nums.next().unwrap(),
-            range_len: nums.next().unwrap(),
+            dest_range_start,
+            src_range_start,
+            range_len,
         };
         Ok(conv)
     }
 }
-#[derive(Debug)]
-struct ConversionTable {
-    conversions: Vec<Conversion>,
+/// Sorted, disjoint source intervals, each carrying the offset applied to
+/// the values that fall inside it. Values outside every interval map to
+/// themselves.
+#[derive(Debug, Default)]
+struct IntervalMap {
+    entries: Vec<(Range<i64>, i64)>,
 }
 
-impl ConversionTable {
-    pub fn from_string_vec(strings: Vec<String>) -> Self {
-        ConversionTable {
-            conversions: strings
-                .iter()
-                .map(|s| Conversion::from_str(s).unwrap())
-                .collect(),
+impl IntervalMap {
+    pub fn insert(&mut self, range: Range<i64>, offset: i64) -> Result<()> {
+        if range.is_empty() {
+            return Ok(());
         }
-    }
-    pub fn convert(&self, n: i64) -> i64 {
-        for conv in &self.conversions {
-            if conv.src_range().contains(&n) {
-                return n - conv.src_range_start + conv.dest_range_start;
+        let idx = self.entries.partition_point(|(r, _)| r.end <= range.start);
+        if let Some((next, _)) = self.entries.get(idx) {
+            if next.start < range.end {
+                return Err(anyhow!("Range {range:?} overlaps {next:?}"));
             }
         }
-        n
+        self.entries.insert(idx, (range, offset));
+        Ok(())
     }
 
-    pub fn convert_range(&self, range: Range<i64>) -> Vec<Range<i64>> {
+    pub fn offset_at(&self, n: i64) -> i64 {
+        let idx = self.entries.partition_point(|(r, _)| r.end <= n);
+        match self.entries.get(idx) {
+            Some((r, offset)) if r.contains(&n) => *offset,
+            _ => 0,
+        }
+    }
+
+    /// Splits `range` at every interval boundary and shifts each piece by the
+    /// offset of the interval it falls into.
+    pub fn map_range(&self, range: Range<i64>) -> Vec<Range<i64>> {
         let mut ranges = vec![];
-        let mut cur_ranges = vec![range];
-        for conv in &self.conversions {
-            let src_range = conv.src_range();
-
-            let mut new_ranges = vec![];
-            for range in cur_ranges.iter() {
-                // [ssssssss]
-                //    [rr]
-                if range.start >= src_range.start && range.end <= src_range.end {
-                    ranges.push(range.start + conv.offset()..range.end + conv.offset());
-                    return ranges;
-                //    [ss]
-                // [rrrrrrrrrr]
-                } else if range.start < src_range.start && range.end > src_range.end {
-                    ranges.push(src_range.start + conv.offset()..src_range.end + conv.offset());
-                    new_ranges.push(range.start..src_range.start);
-                    new_ranges.push(src_range.end..range.end);
-                // [ssssss]
-                //    [rrrrrr]
-                } else if range.start >= src_range.end
-                    && range.start < src_range.end
-                    && range.end > src_range.end
-                {
-                    ranges.push(range.start + conv.offset()..src_range.end + conv.offset());
-                    new_ranges.push(src_range.end..range.end);
-                //     [ssssss]
-                // [rrrrrr]
-                } else if range.end > src_range.start
-                    && range.end <= src_range.end
-                    && range.start < src_range.start
-                {
-                    ranges.push(src_range.start + conv.offset()..range.end + conv.offset());
-                    new_ranges.push(range.start..src_range.start);
-                } else {
-                    new_ranges = cur_ranges.clone();
-                }
+        let mut cur = range.start;
+        let first = self.entries.partition_point(|(r, _)| r.end <= range.start);
+        for (src, offset) in &self.entries[first..] {
+            if src.start >= range.end {
+                break;
+            }
+            if cur < src.start {
+                ranges.push(cur..src.start);
+                cur = src.start;
             }
-            cur_ranges = new_ranges;
+            let end = src.end.min(range.end);
+            ranges.push(cur + offset..end + offset);
+            cur = end;
         }
-        ranges.append(&mut cur_ranges);
-        let ranges = ranges;
-        let result = merge_ranges(ranges);
-
-        result
+        if cur < range.end {
+            ranges.push(cur..range.end);
+        }
+        ranges
     }
 }
 
-fn merge_ranges(ranges: Vec<Range<i64>>) -> Vec<Range<i64>> {
-    let mut ranges = ranges;
-    ranges.sort_by_key(|r| r.start);
-    let mut result = vec![];
-    let mut current_range = ranges[0].clone();
+#[derive(Debug)]
+struct ConversionTable {
+    map: IntervalMap,
+}
 
-    for range in ranges.iter().skip(1) {
-        if range.start <= current_range.end {
-            // Ranges overlap, merge them
-            current_range = current_range.start..range.end.max(current_range.end);
-        } else {
-            // Ranges don't overlap, push the current merged range and update
-            result.push(current_range.clone());
-            current_range = range.clone();
+impl ConversionTable {
+    pub fn from_string_vec(strings: Vec<String>) -> Result<Self> {
+        let mut map = IntervalMap::default();
+        for s in strings {
+            let conv = Conversion::from_str(&s)?;
+            map.insert(conv.src_range(), conv.offset())?;
         }
+        Ok(ConversionTable { map })
     }
-    result.push(current_range);
-    result
-}
 
-pub fn solve_task_one(input: Vec<String>) -> Result<i64> {
-    let start_time = Instant::now();
+    pub fn convert(&self, n: i64) -> i64 {
+        n + self.map.offset_at(n)
+    }
+
+    pub fn convert_range(&self, range: Range<i64>) -> IntervalSet<i64> {
+        self.map.map_range(range).into_iter().collect()
+    }
+
+    pub fn convert_ranges(&self, ranges: &IntervalSet<i64>) -> IntervalSet<i64> {
+        ranges
+            .ranges()
+            .iter()
+            .flat_map(|r| self.convert_range(r.clone()).into_ranges())
+            .collect()
+    }
+}
 
-    let mut result = vec![];
+fn parse_almanac(input: Vec<String>) -> Result<(Vec<i64>, Vec<ConversionTable>)> {
+    let mut blocks = vec![];
     let mut current_block = vec![];
     for line in input {
         if line.is_empty() {
             if !current_block.is_empty() {
-                result.push(current_block.clone());
+                blocks.push(current_block.clone());
                 current_block.clear();
             }
         } else {
@@ -142,101 +141,53 @@ pub fn solve_task_one(input: Vec<String>) -> Result<i64> {
         }
     }
     if !current_block.is_empty() {
-        result.push(current_block);
+        blocks.push(current_block);
     }
-    let conversions = result;
 
-    let seeds = conversions[0][0][6..]
+    let seeds = blocks
+        .first()
+        .and_then(|b| b.first())
+        .and_then(|l| l.strip_prefix("seeds:"))
+        .ok_or(anyhow!("No seeds found"))?
         .split_ascii_whitespace()
-        .map(|e| e.parse::<i64>().unwrap());
-
-    let seed_to_soils = ConversionTable::from_string_vec(conversions[1][1..].into());
-    let soils = seeds.map(|s| seed_to_soils.convert(s));
-    let soil_to_fertilizer = ConversionTable::from_string_vec(conversions[2][1..].into());
-    let fertilizers = soils.map(|s| soil_to_fertilizer.convert(s));
-    let fertilizer_to_water = ConversionTable::from_string_vec(conversions[3][1..].into());
-    let waters = fertilizers.map(|f| fertilizer_to_water.convert(f));
-    let water_to_light = ConversionTable::from_string_vec(conversions[4][1..].into());
-    let lights = waters.map(|w| water_to_light.convert(w));
-    let light_to_temperature = ConversionTable::from_string_vec(conversions[5][1..].into());
-    let temperatures = lights.map(|l| light_to_temperature.convert(l));
-    let temperature_to_humidity = ConversionTable::from_string_vec(conversions[6][1..].into());
-    let humidities = temperatures.map(|t| temperature_to_humidity.convert(t));
-    let humidity_to_location = ConversionTable::from_string_vec(conversions[7][1..].into());
-    let locations = humidities.map(|h| humidity_to_location.convert(h));
-    let sol = locations.min().unwrap();
-    eprintln!("⏱️  Took {:#?}", Instant::now() - start_time);
-    Ok(sol)
+        .map(|e| e.parse::<i64>())
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let tables = blocks
+        .into_iter()
+        .skip(1)
+        .map(|block| ConversionTable::from_string_vec(block[1..].into()))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok((seeds, tables))
 }
 
-pub fn solve_task_two(#[allow(unused_variables)] input: Vec<String>) -> Result<i64> {
+pub fn solve_task_one(input: Vec<String>) -> Result<Answer> {
     let start_time = Instant::now();
+    let (seeds, tables) = parse_almanac(input)?;
 
-    let mut result = vec![];
-    let mut current_block = vec![];
-    for line in input {
-        if line.is_empty() {
-            if !current_block.is_empty() {
-                result.push(current_block.clone());
-                current_block.clear();
-            }
-        } else {
-            current_block.push(line);
-        }
-    }
-    if !current_block.is_empty() {
-        result.push(current_block);
-    }
-    let conversions = result;
+    let locations = seeds
+        .into_iter()
+        .map(|s| tables.iter().fold(s, |n, table| table.convert(n)));
+    let sol = locations.min().ok_or(anyhow!("No seeds"))?;
+    eprintln!("⏱️  Took {:#?}", Instant::now() - start_time);
+    Ok(sol.into())
+}
 
-    let seeds: Vec<_> = conversions[0][0][6..]
-        .split_ascii_whitespace()
-        .map(|e| e.parse::<i64>().unwrap())
-        .collect();
+pub fn solve_task_two(input: Vec<String>) -> Result<Answer> {
+    let start_time = Instant::now();
+    let (seeds, tables) = parse_almanac(input)?;
 
-    let seeds = seeds.par_chunks_exact(2).map(|ch| ch[0]..ch[0] + ch[1]);
-    let seeds_v = seeds.clone().collect::<Vec<_>>();
-    eprintln!("{:#?}", seeds_v);
-    let seeds = seeds.into_par_iter();
-
-    let seed_to_soils = ConversionTable::from_string_vec(conversions[1][1..].into());
-    let soils = merge_ranges(
-        seeds
-            .flat_map(|s| seed_to_soils.convert_range(s))
-            .collect::<Vec<_>>(),
-    );
-
-    let soil_to_fertilizer = ConversionTable::from_string_vec(conversions[2][1..].into());
-    let fertilizers: Vec<_> = soils
-        .into_par_iter()
-        .flat_map(|s| soil_to_fertilizer.convert_range(s))
+    let seeds = seeds
+        .chunks_exact(2)
+        .map(|ch| ch[0]..ch[0] + ch[1])
         .collect();
-    let fertilizers = merge_ranges(fertilizers);
-    eprintln!("{:#?}", fertilizers);
-    let fertilizer_to_water = ConversionTable::from_string_vec(conversions[3][1..].into());
-    let waters = fertilizers
-        .into_par_iter()
-        .flat_map(|f| fertilizer_to_water.convert_range(f));
-    let waters = merge_ranges(waters.collect());
-    let water_to_light = ConversionTable::from_string_vec(conversions[4][1..].into());
-    let lights = waters
-        .into_par_iter()
-        .flat_map(|w| water_to_light.convert_range(w));
-    let lights = merge_ranges(lights.collect()).into_par_iter();
-    let light_to_temperature = ConversionTable::from_string_vec(conversions[5][1..].into());
-    let temperatures = lights.flat_map(|l| light_to_temperature.convert_range(l));
-    let temperatures = merge_ranges(temperatures.collect()).into_par_iter();
-    let temperature_to_humidity = ConversionTable::from_string_vec(conversions[6][1..].into());
-    let humidities = temperatures.flat_map(|t| temperature_to_humidity.convert_range(t));
-    let humidities = merge_ranges(humidities.collect()).into_par_iter();
-    let humidity_to_location = ConversionTable::from_string_vec(conversions[7][1..].into());
-    let locations = humidities.flat_map(|h| humidity_to_location.convert_range(h));
-    let locations = merge_ranges(locations.collect()).into_par_iter();
-    let locations = locations.map(|r| r.start);
-    // eprintln!("{:#?}", locations);
-    let sol = locations.min().unwrap();
+    let locations = tables
+        .iter()
+        .fold(seeds, |ranges, table| table.convert_ranges(&ranges));
+    let sol = locations.min().ok_or(anyhow!("No seeds"))?;
     eprintln!("⏱️  Took {:#?}", Instant::now() - start_time);
-    Ok(sol)
+    Ok(sol.into())
 }
 
 #[cfg(test)]
@@ -256,7 +207,8 @@ mod test {
         Ok(lines?)
     }
 
-    use crate::{merge_ranges, solve_task_one, solve_task_two, ConversionTable};
+    use crate::{solve_task_one, solve_task_two, ConversionTable};
+    use common::interval::IntervalSet;
 
     #[test]
     fn test_case_one_example() -> Result<()> {
@@ -291,51 +243,67 @@ mod test {
         Ok(())
     }
 
-    #[test]
-    fn test_range_merge() -> Result<()> {
-        let merged = merge_ranges(vec![50..52, 52..100, 1..50]);
-        assert_eq!(merged, vec![1..100]);
-        Ok(())
-    }
-
     #[test]
     fn test_range_convert() -> Result<()> {
         let ct =
-            ConversionTable::from_string_vec(vec!["50 98 2".to_string(), "52 50 48".to_string()]);
-        // let r1 = 98..100;
-        // assert_eq!(ct.convert_range(r1), vec![50..52]);
+            ConversionTable::from_string_vec(vec!["50 98 2".to_string(), "52 50 48".to_string()])?;
+        let r1 = 98..100;
+        assert_eq!(ct.convert_range(r1), IntervalSet::from(50..52));
+
+        let r2 = 90..99;
+        assert_eq!(ct.convert_range(r2).ranges(), &[50..51, 92..100]);
 
-        // let r2 = 90..99;
-        // assert_eq!(ct.convert_range(r2), vec![50..51, 92..100]);
+        let r3 = 1..100;
+        assert_eq!(ct.convert_range(r3), IntervalSet::from(1..100));
 
-        // let r3 = 1..100;
-        // assert_eq!(ct.convert_range(r3), vec![1..100]);
+        let r5 = 95..110;
+        assert_eq!(ct.convert_range(r5).ranges(), &[50..52, 97..110]);
 
         let r4 = 79..93;
-        assert_eq!(ct.convert_range(r4), vec![81..95]);
+        assert_eq!(ct.convert_range(r4), IntervalSet::from(81..95));
 
         Ok(())
     }
 
     #[test]
-    fn test_merge_and_convert() {
+    fn test_merge_and_convert() -> Result<()> {
         let ct = ConversionTable::from_string_vec(vec![
             "0 15 37".to_string(),
             "37 52 2".to_string(),
             "39 0 15".to_string(),
-        ]);
+        ])?;
 
         let ranges = vec![57..70, 81..95];
 
         assert_eq!(
-            merge_ranges(
-                ranges
-                    .clone()
-                    .into_iter()
-                    .flat_map(|r| ct.convert_range(r))
-                    .collect()
-            ),
             ranges
+                .iter()
+                .flat_map(|r| ct.convert_range(r.clone()).into_ranges())
+                .collect::<IntervalSet<_>>()
+                .ranges(),
+            &ranges
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_overlapping_conversions() {
+        assert!(ConversionTable::from_string_vec(vec![
+            "0 10 5".to_string(),
+            "20 12 5".to_string()
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_convert_ranges_chained() -> Result<()> {
+        let ct =
+            ConversionTable::from_string_vec(vec!["0 10 5".to_string(), "100 20 5".to_string()])?;
+        let ranges = vec![8..12, 14..22, 30..31].into_iter().collect();
+        assert_eq!(
+            ct.convert_ranges(&ranges).ranges(),
+            &[0..2, 4..5, 8..10, 15..20, 30..31, 100..102]
         );
+        Ok(())
     }
 }
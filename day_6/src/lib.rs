@@ -1,59 +1,73 @@
 use std::time::Instant;
 
 use color_eyre::Result;
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use common::answer::{Answer, CheckedIterator};
+
+/// Number of charge times that beat the record distance.
+///
+/// Charging for `c` ms travels `c * (time - c)`, so the winning charge times
+/// are the integers strictly between the roots of `c² - time·c + record = 0`.
+/// The integer square root only gives an estimate of the lower root, which is
+/// then nudged so that charge times that exactly match the record are excluded.
+fn winning_charge_times(time: i64, record: i64) -> i64 {
+    let beats_record = |charge: i64| (charge as i128) * ((time - charge) as i128) > record as i128;
+    let discriminant = (time as i128) * (time as i128) - 4 * (record as i128);
+    if discriminant < 0 {
+        return 0;
+    }
+    let mut low = ((time as i128 - discriminant.isqrt()) / 2) as i64;
+    while low > 0 && beats_record(low - 1) {
+        low -= 1;
+    }
+    while low <= time / 2 && !beats_record(low) {
+        low += 1;
+    }
+    // The distance is symmetric around time / 2
+    let high = time - low;
+    (high - low + 1).max(0)
+}
 
-pub fn solve_task_one(input: Vec<String>) -> Result<i32> {
+pub fn solve_task_one(input: Vec<String>) -> Result<Answer> {
     let start_time = Instant::now();
     let times = input[0]
         .split_ascii_whitespace()
         .skip(1)
-        .map(|t| t.parse::<i32>().unwrap());
+        .map(|t| t.parse::<i64>())
+        .collect::<std::result::Result<Vec<_>, _>>()?;
 
     let distances = input[1]
         .split_ascii_whitespace()
         .skip(1)
-        .map(|t| t.parse::<i32>().unwrap());
-
-    let races = times.zip(distances);
-    let sol = races
-        .map(|(t, d)| {
-            (1..=t)
-                .map(|charge| {
-                    let race = t - charge;
-                    let dist = race * charge;
-                    dist
-                })
-                .filter(|race_dist| *race_dist > d)
-                .count() as i32
-        })
-        .product();
+        .map(|t| t.parse::<i64>())
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let sol: i64 = times
+        .into_iter()
+        .zip(distances)
+        .map(|(t, d)| winning_charge_times(t, d))
+        .try_product()?;
     eprintln!("⏱️  Took {:#?}", Instant::now() - start_time);
-    Ok(sol)
+    Ok(sol.into())
 }
 
-pub fn solve_task_two(#[allow(unused_variables)] input: Vec<String>) -> Result<i64> {
+pub fn solve_task_two(input: Vec<String>) -> Result<Answer> {
     let start_time = Instant::now();
     let time: i64 = input[0]
         .split_ascii_whitespace()
         .skip(1)
-        .fold("".to_string(), |a, b| format!("{}{}", a, b))
+        .collect::<String>()
         .parse()?;
 
     let record_distance: i64 = input[1]
         .split_ascii_whitespace()
         .skip(1)
-        .fold("".to_string(), |a, b| format!("{}{}", a, b))
+        .collect::<String>()
         .parse()?;
 
-    let sol: i64 = (1..=time)
-        .into_par_iter()
-        .map(|ch_time| ch_time * (time - ch_time))
-        .filter(|race_dist| record_distance < *race_dist)
-        .count() as i64;
+    let sol = winning_charge_times(time, record_distance);
 
     eprintln!("⏱️  Took {:#?}", Instant::now() - start_time);
-    Ok(sol)
+    Ok(sol.into())
 }
 
 #[cfg(test)]
@@ -73,7 +87,7 @@ mod test {
         Ok(lines?)
     }
 
-    use crate::{solve_task_one, solve_task_two};
+    use crate::{solve_task_one, solve_task_two, winning_charge_times};
 
     #[test]
     fn test_case_one_example() -> Result<()> {
@@ -108,4 +122,15 @@ mod test {
         assert_eq!(solve_task_two(file)?, 20537782);
         Ok(())
     }
+
+    #[test]
+    fn test_winning_charge_times() {
+        assert_eq!(winning_charge_times(7, 9), 4);
+        assert_eq!(winning_charge_times(15, 40), 8);
+        // Charging 10ms or 20ms exactly matches the record
+        assert_eq!(winning_charge_times(30, 200), 9);
+        assert_eq!(winning_charge_times(4, 4), 0);
+        assert_eq!(winning_charge_times(3, 10), 0);
+        assert_eq!(winning_charge_times(71530, 940200), 71503);
+    }
 }
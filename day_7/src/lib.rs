@@ -1,34 +1,10 @@
-use std::{cmp::Ordering, collections::HashMap, str::FromStr, time::Instant};
+use std::{cmp::Ordering, time::Instant};
 
-use color_eyre::Result;
-use rayon::slice::ParallelSliceMut;
+use color_eyre::{eyre::anyhow, Result};
+use common::answer::{Answer, Checked, CheckedIterator};
 
-static ALL_CARDS: [char; 13] = [
-    'A', 'K', 'Q', 'J', 'T', '9', '8', '7', '6', '5', '4', '3', '2',
-];
-
-static ALL_CARDS_TWO: [char; 13] = [
-    'A', 'K', 'Q', 'T', '9', '8', '7', '6', '5', '4', '3', '2', 'J',
-];
-
-#[derive(Debug, PartialEq, Eq, Ord, Clone, Copy)]
-struct Hand {
-    cards: [char; 5],
-}
-
-impl FromStr for Hand {
-    type Err = color_eyre::Report;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut cards = [0 as char; 5];
-        for (i, c) in s.chars().enumerate() {
-            cards[i] = c;
-        }
-        Ok(Hand { cards })
-    }
-}
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
-enum HandType {
+pub enum HandType {
     HighCard,
     Pair,
     TwoPair,
@@ -38,221 +14,186 @@ enum HandType {
     FiveOfAKind,
 }
 
-impl Hand {
-    pub fn hand_type(&self) -> HandType {
-        let mut counts: HashMap<_, _> = ALL_CARDS.iter().map(|c| (*c, 0)).collect();
-        for c in self.cards.iter() {
-            counts.insert(*c, counts[c] + 1);
-        }
-        let mut pairs = 0;
-        let mut three_of_a_kind = false;
-        let mut four_of_a_kind = false;
-        let mut five_of_a_kind = false;
-        for (_c, instances) in counts.into_iter() {
-            if instances == 2 {
-                pairs += 1;
-            } else if instances == 3 {
-                three_of_a_kind = true;
-            } else if instances == 4 {
-                four_of_a_kind = true;
-            } else if instances == 5 {
-                five_of_a_kind = true;
-            }
+impl HandType {
+    /// Classifies a hand from how often each card appears, wild cards
+    /// joining the most common other card.
+    fn from_counts(mut counts: Vec<u8>, wild: u8) -> Self {
+        counts.sort_unstable_by(|a, b| b.cmp(a));
+        match counts.first_mut() {
+            Some(most) => *most += wild,
+            None => counts.push(wild),
         }
-        if five_of_a_kind {
-            HandType::FiveOfAKind
-        } else if four_of_a_kind {
-            HandType::FourOfAKind
-        } else if three_of_a_kind && pairs == 1 {
-            HandType::FullHouse
-        } else if three_of_a_kind {
-            HandType::ThreeOfAKind
-        } else if pairs == 2 {
-            HandType::TwoPair
-        } else if pairs == 1 {
-            HandType::Pair
-        } else {
-            HandType::HighCard
+        match counts[..] {
+            [5, ..] => HandType::FiveOfAKind,
+            [4, ..] => HandType::FourOfAKind,
+            [3, 2, ..] => HandType::FullHouse,
+            [3, ..] => HandType::ThreeOfAKind,
+            [2, 2, ..] => HandType::TwoPair,
+            [2, ..] => HandType::Pair,
+            _ => HandType::HighCard,
         }
     }
 }
 
-impl PartialOrd for Hand {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        if let Some(ordering) = self.hand_type().partial_cmp(&other.hand_type()) {
-            match ordering {
-                Ordering::Less => Some(Ordering::Less),
-                Ordering::Greater => Some(Ordering::Greater),
-                Ordering::Equal => {
-                    for (s, o) in self.cards.into_iter().zip(other.cards) {
-                        let (self_idx, _) = ALL_CARDS
-                            .iter()
-                            .enumerate()
-                            .find(|(_idx, ch)| **ch == s)
-                            .unwrap();
-                        let (other_idx, _) = ALL_CARDS
-                            .iter()
-                            .enumerate()
-                            .find(|(_, ch)| **ch == o)
-                            .unwrap();
-                        match self_idx.cmp(&other_idx) {
-                            Ordering::Less => return Some(Ordering::Greater),
-                            Ordering::Equal => (),
-                            Ordering::Greater => return Some(Ordering::Less),
-                        }
-                    }
-                    Some(Ordering::Equal)
-                }
-            }
-        } else {
-            None
-        }
-    }
+/// How hands of the same type are ordered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreak {
+    /// Compare the cards in the order they were dealt
+    DealtOrder,
+    /// Compare the cards from the strongest to the weakest, like poker
+    StrongestFirst,
 }
-#[derive(Debug, PartialEq, Eq, Ord, Clone, Copy)]
-struct HandWithJoker {
-    cards: [char; 5],
+
+/// A variant of Camel Cards
+#[derive(Debug, Clone, Copy)]
+pub struct Rules {
+    /// Every card from the strongest to the weakest
+    pub card_order: &'static [char],
+    /// Cards that act as whatever card makes the hand strongest
+    pub wild_cards: &'static [char],
+    pub tie_break: TieBreak,
 }
 
-impl FromStr for HandWithJoker {
-    type Err = color_eyre::Report;
+/// The rules of part one
+pub const STANDARD_RULES: Rules = Rules {
+    card_order: &[
+        'A', 'K', 'Q', 'J', 'T', '9', '8', '7', '6', '5', '4', '3', '2',
+    ],
+    wild_cards: &[],
+    tie_break: TieBreak::DealtOrder,
+};
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut cards = [0 as char; 5];
-        for (i, c) in s.chars().enumerate() {
-            cards[i] = c;
-        }
-        Ok(HandWithJoker { cards })
+/// The rules of part two, `J` is a joker and the weakest card
+pub const JOKER_RULES: Rules = Rules {
+    card_order: &[
+        'A', 'K', 'Q', 'T', '9', '8', '7', '6', '5', '4', '3', '2', 'J',
+    ],
+    wild_cards: &['J'],
+    tie_break: TieBreak::DealtOrder,
+};
+
+impl Rules {
+    /// Higher is stronger
+    fn strength(&self, card: char) -> Result<u8> {
+        let idx = self
+            .card_order
+            .iter()
+            .position(|&c| c == card)
+            .ok_or(anyhow!("Unknown card {card}"))?;
+        Ok((self.card_order.len() - idx) as u8)
     }
 }
 
-impl HandWithJoker {
-    pub fn hand_type(&self) -> HandType {
-        let mut counts: HashMap<_, _> = ALL_CARDS_TWO.iter().map(|c| (*c, 0)).collect();
-        for c in self.cards.iter() {
-            counts.insert(*c, counts[c] + 1);
-        }
-        let mut pairs = 0;
-        let mut three_of_a_kind = false;
-        let mut four_of_a_kind = false;
-        let mut five_of_a_kind = false;
-        let mut jokers = 0;
-        for (c, instances) in counts.into_iter() {
-            if c == 'J' {
-                jokers = instances
-            } else if instances == 2 {
-                pairs += 1;
-            } else if instances == 3 {
-                three_of_a_kind = true;
-            } else if instances == 4 {
-                four_of_a_kind = true;
-            } else if instances == 5 {
-                five_of_a_kind = true;
+/// A hand scored under some rules. Its type and tie-break are packed into a
+/// single key when it is dealt, so comparing hands is one integer compare.
+/// Hands of the same rank compare equal.
+#[derive(Debug, Clone, Copy)]
+pub struct Hand {
+    cards: [char; 5],
+    hand_type: HandType,
+    key: u64,
+}
+
+impl Hand {
+    pub fn new(s: &str, rules: &Rules) -> Result<Self> {
+        let cards: [char; 5] = s
+            .chars()
+            .collect::<Vec<_>>()
+            .try_into()
+            .map_err(|_| anyhow!("A hand has five cards: {s}"))?;
+
+        let mut strengths = cards
+            .iter()
+            .map(|&c| rules.strength(c))
+            .collect::<Result<Vec<_>>>()?;
+        let mut counts = vec![0; rules.card_order.len() + 1];
+        let mut wild = 0;
+        for (&card, &strength) in cards.iter().zip(&strengths) {
+            if rules.wild_cards.contains(&card) {
+                wild += 1;
+            } else {
+                counts[strength as usize] += 1;
             }
         }
+        counts.retain(|&count| count > 0);
+        let hand_type = HandType::from_counts(counts, wild);
 
-        if five_of_a_kind
-            || four_of_a_kind && jokers == 1
-            || three_of_a_kind && jokers == 2
-            || pairs == 1 && jokers == 3
-            || jokers >= 4
-        {
-            HandType::FiveOfAKind
-        } else if four_of_a_kind
-            || three_of_a_kind && jokers == 1
-            || pairs == 1 && jokers == 2
-            || pairs == 0 && jokers == 3
-        {
-            HandType::FourOfAKind
-        } else if three_of_a_kind && pairs == 1
-            || pairs == 2 && jokers == 1
-            || pairs == 1 && jokers == 2
-        {
-            HandType::FullHouse
-        } else if three_of_a_kind || pairs == 1 && jokers == 1 || pairs == 0 && jokers == 2 {
-            HandType::ThreeOfAKind
-        } else if pairs == 2 {
-            HandType::TwoPair
-        } else if pairs == 1 || pairs == 0 && jokers == 1 {
-            HandType::Pair
-        } else {
-            HandType::HighCard
+        if rules.tie_break == TieBreak::StrongestFirst {
+            strengths.sort_unstable_by(|a, b| b.cmp(a));
         }
+        let key = strengths
+            .into_iter()
+            .fold(hand_type as u64, |key, strength| key << 8 | strength as u64);
+        Ok(Hand {
+            cards,
+            hand_type,
+            key,
+        })
+    }
+
+    pub fn cards(&self) -> &[char; 5] {
+        &self.cards
+    }
+
+    pub fn hand_type(&self) -> HandType {
+        self.hand_type
     }
 }
 
-impl PartialOrd for HandWithJoker {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        let ordering = self.hand_type().cmp(&other.hand_type());
-        match ordering {
-            Ordering::Less => Some(Ordering::Less),
-            Ordering::Greater => Some(Ordering::Greater),
-            Ordering::Equal => {
-                for (s, o) in self.cards.into_iter().zip(other.cards) {
-                    let (self_idx, _) = ALL_CARDS_TWO
-                        .iter()
-                        .enumerate()
-                        .find(|(_idx, ch)| **ch == s)
-                        .unwrap();
-                    let (other_idx, _) = ALL_CARDS_TWO
-                        .iter()
-                        .enumerate()
-                        .find(|(_, ch)| **ch == o)
-                        .unwrap();
-                    match self_idx.cmp(&other_idx) {
-                        Ordering::Less => return Some(Ordering::Greater),
-                        Ordering::Equal => (),
-                        Ordering::Greater => return Some(Ordering::Less),
-                    }
-                }
-                Some(Ordering::Equal)
-            }
-        }
+impl PartialEq for Hand {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
     }
 }
 
-pub fn solve_task_one(input: Vec<String>) -> Result<i32> {
-    let start_time = Instant::now();
-    let mut hands: Vec<_> = input
+impl Eq for Hand {}
+
+impl PartialOrd for Hand {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Hand {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+/// Sum of every bid multiplied by the rank of its hand under `rules`
+pub fn total_winnings(input: &[String], rules: &Rules) -> Result<i64> {
+    let mut hands = input
         .iter()
-        .map(|s| {
-            let mut parts = s.split_ascii_whitespace();
-            let hand: Hand = parts.next().unwrap().parse().unwrap();
-            let bid: i32 = parts.next().unwrap().parse().unwrap();
-            (hand, bid)
+        .map(|line| {
+            let (hand, bid) = line
+                .split_once(' ')
+                .ok_or(anyhow!("Invalid line: {line}"))?;
+            Ok((Hand::new(hand, rules)?, bid.trim().parse::<i64>()?))
         })
-        .collect();
+        .collect::<Result<Vec<_>>>()?;
 
-    hands.par_sort_by_key(|(h, _)| *h);
-    let sol: i32 = hands
+    hands.sort_unstable_by_key(|(hand, _)| hand.key);
+    hands
         .iter()
         .enumerate()
-        .map(|(idx, (_, bid))| (idx as i32 + 1) * bid)
-        .sum();
-    eprintln!("⏱️ Took: {:?}", start_time.elapsed());
-    Ok(sol)
+        .map(|(idx, (_, bid))| (idx as i64 + 1).try_mul(*bid))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .try_sum()
 }
 
-pub fn solve_task_two(#[allow(unused_variables)] input: Vec<String>) -> Result<i32> {
+pub fn solve_task_one(input: Vec<String>) -> Result<Answer> {
     let start_time = Instant::now();
-    let mut hands: Vec<_> = input
-        .iter()
-        .map(|s| {
-            let mut parts = s.split_ascii_whitespace();
-            let hand: HandWithJoker = parts.next().unwrap().parse().unwrap();
-            let bid: i32 = parts.next().unwrap().parse().unwrap();
-            (hand, bid)
-        })
-        .collect();
+    let sol = total_winnings(&input, &STANDARD_RULES)?;
+    eprintln!("⏱️ Took: {:?}", start_time.elapsed());
+    Ok(sol.into())
+}
 
-    hands.par_sort_by_key(|(h, _)| *h);
-    let sol: i32 = hands
-        .iter()
-        .enumerate()
-        .map(|(idx, (_, bid))| (idx as i32 + 1) * bid)
-        .sum();
+pub fn solve_task_two(input: Vec<String>) -> Result<Answer> {
+    let start_time = Instant::now();
+    let sol = total_winnings(&input, &JOKER_RULES)?;
     eprintln!("⏱️ Took: {:?}", start_time.elapsed());
-    Ok(sol)
+    Ok(sol.into())
 }
 
 #[cfg(test)]
@@ -272,7 +213,10 @@ mod test {
         Ok(lines?)
     }
 
-    use crate::{solve_task_one, solve_task_two, Hand, HandType, HandWithJoker};
+    use crate::{
+        solve_task_one, solve_task_two, total_winnings, Hand, HandType, Rules, TieBreak,
+        JOKER_RULES, STANDARD_RULES,
+    };
 
     #[test]
     fn test_case_one_example() -> Result<()> {
@@ -322,36 +266,11 @@ mod test {
     #[test]
     fn test_hand_sort() {
         let hands = [
-            (
-                Hand {
-                    cards: ['K', 'K', '6', '7', '7'],
-                },
-                28,
-            ),
-            (
-                Hand {
-                    cards: ['K', 'T', 'J', 'J', 'T'],
-                },
-                220,
-            ),
-            (
-                Hand {
-                    cards: ['T', '5', '5', 'J', '5'],
-                },
-                684,
-            ),
-            (
-                Hand {
-                    cards: ['Q', 'Q', 'Q', 'J', 'A'],
-                },
-                483,
-            ),
-            (
-                Hand {
-                    cards: ['3', '2', 'T', '3', 'K'],
-                },
-                765,
-            ),
+            (Hand::new("KK677", &STANDARD_RULES).unwrap(), 28),
+            (Hand::new("KTJJT", &STANDARD_RULES).unwrap(), 220),
+            (Hand::new("T55J5", &STANDARD_RULES).unwrap(), 684),
+            (Hand::new("QQQJA", &STANDARD_RULES).unwrap(), 483),
+            (Hand::new("32T3K", &STANDARD_RULES).unwrap(), 765),
         ];
         // assert!(hands[4] < hands[0]);
         // assert!(hands[4] < hands[1]);
@@ -362,19 +281,48 @@ mod test {
 
     #[test]
     fn test_joker() {
-        let hand: HandWithJoker = "J9Q77".parse().unwrap();
+        let hand = Hand::new("J9Q77", &JOKER_RULES).unwrap();
         assert_eq!(hand.hand_type(), HandType::ThreeOfAKind);
-        let hand: HandWithJoker = "7227J".parse().unwrap();
+        let hand = Hand::new("7227J", &JOKER_RULES).unwrap();
         assert_eq!(hand.hand_type(), HandType::FullHouse);
-        let hand: HandWithJoker = "J44JJ".parse().unwrap();
+        let hand = Hand::new("J44JJ", &JOKER_RULES).unwrap();
         assert_eq!(hand.hand_type(), HandType::FiveOfAKind);
-        let hand: HandWithJoker = "4J888".parse().unwrap();
+        let hand = Hand::new("4J888", &JOKER_RULES).unwrap();
         assert_eq!(hand.hand_type(), HandType::FourOfAKind);
-        let hand: HandWithJoker = "KTJJT".parse().unwrap();
+        let hand = Hand::new("KTJJT", &JOKER_RULES).unwrap();
         assert_eq!(hand.hand_type(), HandType::FourOfAKind);
 
-        let hand: HandWithJoker = "J9Q77".parse().unwrap();
-        let hand_2: HandWithJoker = "9JQ77".parse().unwrap();
+        let hand = Hand::new("J9Q77", &JOKER_RULES).unwrap();
+        let hand_2 = Hand::new("9JQ77", &JOKER_RULES).unwrap();
         assert!(hand < hand_2);
     }
+
+    #[test]
+    fn test_rule_sets() -> Result<()> {
+        let hand = Hand::new("KTJJT", &STANDARD_RULES)?;
+        assert_eq!(hand.hand_type(), HandType::TwoPair);
+        let hand = Hand::new("JJJJJ", &JOKER_RULES)?;
+        assert_eq!(hand.hand_type(), HandType::FiveOfAKind);
+        assert!(Hand::new("JJJJJ", &JOKER_RULES)? < Hand::new("22222", &JOKER_RULES)?);
+        assert!(Hand::new("AAJ1K", &STANDARD_RULES).is_err());
+        assert!(Hand::new("AAJK", &STANDARD_RULES).is_err());
+
+        let poker = Rules {
+            card_order: STANDARD_RULES.card_order,
+            wild_cards: &['2'],
+            tie_break: TieBreak::StrongestFirst,
+        };
+        assert_eq!(
+            Hand::new("2KQ23", &poker)?.hand_type(),
+            HandType::ThreeOfAKind
+        );
+        // Same type, decided by the highest card instead of the first one
+        assert!(Hand::new("3456A", &poker)? > Hand::new("KQJ98", &poker)?);
+        assert!(Hand::new("3456A", &STANDARD_RULES)? < Hand::new("KQJ98", &STANDARD_RULES)?);
+
+        let lines = ["32T3K 765", "T55J5 684"].map(String::from);
+        assert_eq!(total_winnings(&lines, &STANDARD_RULES)?, 765 + 684 * 2);
+        assert!(total_winnings(&["32T3K".to_string()], &STANDARD_RULES).is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,30 @@
+use std::path::PathBuf;
+
+use common::get_file;
+use criterion::{criterion_group, criterion_main, Criterion};
+use solution::Network;
+
+fn bench_network(c: &mut Criterion) {
+    let input = get_file(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("inputs/full.txt"))
+        .expect("the personal input exists");
+    c.bench_function("parse", |b| b.iter(|| Network::from_lines(&input).unwrap()));
+
+    let network = Network::from_lines(&input).unwrap();
+    let starts: Vec<String> = network
+        .nodes()
+        .filter(|node| node.ends_with('A'))
+        .map(String::from)
+        .collect();
+    c.bench_function("ghost cycles", |b| {
+        b.iter(|| {
+            for start in &starts {
+                network
+                    .ghost_cycle(start, |node| node.ends_with('Z'))
+                    .unwrap();
+            }
+        })
+    });
+}
+
+criterion_group!(benches, bench_network);
+criterion_main!(benches);
@@ -1,177 +1,208 @@
-use color_eyre::Result;
-use std::{collections::HashMap, time::Instant};
+use color_eyre::{eyre::anyhow, Result};
+use common::{
+    answer::Answer,
+    graph::{Graph, GraphBuilder, Id},
+    math::crt,
+};
+use std::time::Instant;
 
 static START_NODE: &str = "AAA";
 static END_NODE: &str = "ZZZ";
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-struct Node {
-    pub value: String,
-    left: String,
-    right: String,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Left,
+    Right,
 }
 
-impl<'a> Node {
-    fn new(value: String, left: String, right: String) -> Self {
-        Self { value, left, right }
+impl TryFrom<char> for Direction {
+    type Error = color_eyre::eyre::Error;
+
+    fn try_from(value: char) -> Result<Self> {
+        match value {
+            'L' => Ok(Direction::Left),
+            'R' => Ok(Direction::Right),
+            _ => Err(anyhow!("Invalid direction {value}")),
+        }
     }
 }
 
-pub fn solve_task_one(input: Vec<String>) -> Result<i64> {
-    let start_time = Instant::now();
-    let mut o_input = input.iter();
-    let dirs = o_input.next().unwrap().clone();
-
-    let raw_nodes = input
-        .into_iter()
-        .skip(2)
-        .map(|el| {
-            let mut parts = el.split("=");
-            let pars: &[_] = &['(', ')'];
-            (
-                parts.next().unwrap().trim().to_string(),
-                parts
-                    .next()
-                    .unwrap()
-                    .trim()
-                    .trim_matches(pars)
-                    .split(',')
-                    .map(|part| part.trim().to_string())
-                    .collect::<Vec<_>>(),
-            )
-        })
-        .map(|(v, lrs)| (v, (lrs[0].clone(), lrs[1].clone())))
-        .collect::<Vec<_>>();
+/// The instructions and the left and right neighbours of every node
+#[derive(Debug, Clone)]
+pub struct Network {
+    instructions: Vec<Direction>,
+    nodes: Graph<(Id, Id)>,
+}
 
-    let mut nodes: HashMap<String, Node> = HashMap::new();
+impl Network {
+    pub fn from_lines(lines: &[String]) -> Result<Self> {
+        let (instructions, nodes) = lines
+            .split_first()
+            .ok_or_else(|| anyhow!("No instructions"))?;
+        let instructions = instructions
+            .chars()
+            .map(Direction::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        if instructions.is_empty() {
+            return Err(anyhow!("No instructions"));
+        }
+        let mut builder = GraphBuilder::new();
+        for line in nodes.iter().filter(|line| !line.is_empty()) {
+            let (node, neighbours) = line
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Invalid node {line}"))?;
+            let (left, right) = neighbours
+                .trim()
+                .trim_matches(['(', ')'])
+                .split_once(',')
+                .ok_or_else(|| anyhow!("Invalid neighbours in {line}"))?;
+            let neighbours = (builder.id(left.trim()), builder.id(right.trim()));
+            builder.define(node.trim(), neighbours)?;
+        }
+        Ok(Self {
+            instructions,
+            nodes: builder.build()?,
+        })
+    }
 
-    for (v, (l, r)) in raw_nodes.into_iter() {
-        nodes.insert(v.clone(), Node::new(v, l, r));
+    pub fn nodes(&self) -> impl Iterator<Item = &str> + '_ {
+        self.nodes.names().iter().map(|(_, name)| name)
     }
 
-    let mut reps = 0;
-    let mut cur_node = &nodes[START_NODE];
-    let end_node = &nodes[END_NODE];
+    fn next(&self, node: Id, position: usize) -> Id {
+        let (left, right) = self.nodes[node];
+        match self.instructions[position] {
+            Direction::Left => left,
+            Direction::Right => right,
+        }
+    }
 
-    while cur_node != end_node {
-        reps += 1;
-        for d in dirs.chars() {
-            match d {
-                'L' => cur_node = &nodes[&nodes[&cur_node.value].left],
-                'R' => cur_node = &nodes[&nodes[&cur_node.value].right],
-                _ => panic!(),
+    /// Walks from `start` until a `(node, instruction position)` state
+    /// repeats, recording each step that ends on a node where `is_goal` holds.
+    pub fn ghost_cycle(&self, start: &str, is_goal: impl Fn(&str) -> bool) -> Result<GhostCycle> {
+        let mut node = self
+            .nodes
+            .id(start)
+            .ok_or_else(|| anyhow!("No node {start}"))?;
+        let goals: Vec<bool> = self
+            .nodes
+            .ids()
+            .map(|id| is_goal(self.nodes.name(id)))
+            .collect();
+        let positions = self.instructions.len();
+        // Step on which each state was first seen, indexed by node and position
+        let mut first_seen = vec![None; self.nodes.len() * positions];
+        let mut hits = vec![];
+        for step in 0.. {
+            let position = step % positions;
+            let state = node.index() * positions + position;
+            if let Some(tail) = first_seen[state] {
+                let split = hits.partition_point(|&hit| hit < tail);
+                let cycle_hits = hits.split_off(split);
+                return Ok(GhostCycle {
+                    tail,
+                    period: step - tail,
+                    tail_hits: hits,
+                    cycle_hits,
+                });
             }
+            first_seen[state] = Some(step);
+            if goals[node.index()] {
+                hits.push(step);
+            }
+            node = self.next(node, position);
         }
+        unreachable!("the number of states is finite")
     }
-
-    eprintln!("{:?}", Instant::now() - start_time);
-    Ok(reps * dirs.len() as i64)
 }
 
-fn gcd(a: i64, b: i64) -> i64 {
-    if b == 0 {
-        a.abs()
-    } else {
-        gcd(b, a % b)
-    }
+/// Steps on which a ghost stands on a goal node. After `tail` steps it's in a
+/// loop of `period` steps, so a step `t >= tail` is a hit iff
+/// `tail + (t - tail) % period` is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GhostCycle {
+    pub tail: usize,
+    pub period: usize,
+    /// Sorted hits before `tail`
+    pub tail_hits: Vec<usize>,
+    /// Sorted hits in `tail..tail + period`
+    pub cycle_hits: Vec<usize>,
 }
 
-fn lcm(a: i64, b: i64) -> i64 {
-    if a == 0 || b == 0 {
-        0
-    } else {
-        (a.abs() / gcd(a, b)) * b.abs()
+impl GhostCycle {
+    pub fn is_hit(&self, step: usize) -> bool {
+        if step < self.tail {
+            self.tail_hits.binary_search(&step).is_ok()
+        } else {
+            let step = self.tail + (step - self.tail) % self.period;
+            self.cycle_hits.binary_search(&step).is_ok()
+        }
     }
 }
 
-pub fn solve_task_two(#[allow(unused_variables)] input: Vec<String>) -> Result<i64> {
-    let start_time = Instant::now();
-    let mut o_input = input.iter();
-    let dirs = o_input.next().unwrap().clone();
-
-    let raw_nodes = input
-        .into_iter()
-        .skip(2)
-        .map(|el| {
-            let mut parts = el.split("=");
-            let pars: &[_] = &['(', ')'];
-            let trim = parts.next().unwrap().trim().to_owned();
-            let mut map = parts
-                .next()
-                .unwrap()
-                .trim()
-                .trim_matches(pars)
-                .split(',')
-                .map(|part| part.trim().to_owned());
-            (trim, map.next().unwrap(), map.next().unwrap())
-        })
-        .map(|(v, l, r)| (v, (l, r)))
-        .collect::<Vec<_>>();
-
-    let mut nodes: HashMap<String, Node> = HashMap::new();
-    for (v, (l, r)) in raw_nodes.into_iter() {
-        nodes.insert(v.clone(), Node::new(v, l, r));
+/// First step on which every ghost stands on a goal node. Before the longest
+/// tail the steps are checked one by one, after it every choice of one cycle
+/// hit per ghost gives a system of congruences.
+pub fn first_common_step(cycles: &[GhostCycle]) -> Result<u64> {
+    if cycles.is_empty() {
+        return Err(anyhow!("No ghosts to move"));
+    }
+    let longest_tail = cycles.iter().map(|c| c.tail).max().unwrap_or(0);
+    if let Some(step) = (0..longest_tail).find(|&step| cycles.iter().all(|c| c.is_hit(step))) {
+        return Ok(step as u64);
     }
 
-    let start_nodes: Vec<_> = nodes
+    let mut choices: Vec<Vec<(i128, i128)>> = vec![vec![]];
+    for cycle in cycles {
+        choices = choices
+            .into_iter()
+            .flat_map(|choice| {
+                cycle.cycle_hits.iter().map(move |&hit| {
+                    let mut choice = choice.clone();
+                    choice.push((hit as i128, cycle.period as i128));
+                    choice
+                })
+            })
+            .collect();
+    }
+    let longest_tail = longest_tail as i128;
+    let step = choices
         .iter()
-        .filter(|n| n.0.ends_with('A'))
-        .map(|n| n.1.clone())
-        .collect();
-
-    let dirs: Vec<char> = dirs.chars().collect();
-
-    let circle_lens = start_nodes.iter().map(|n| {
-        let mut n = n;
-        let mut end_node = None;
-        let end_node = loop {
-            let mut dirs = dirs.iter().enumerate();
-
-            while let Some((idx, dir)) = dirs.next() {
-                if n.value.ends_with("Z") {
-                    end_node = Some((idx, n));
-                    break;
-                }
-                match dir {
-                    'L' => n = &nodes[&n.left],
-                    'R' => n = &nodes[&n.right],
-                    _ => panic!("wrong char in dirs {dir}"),
-                }
+        .filter_map(|congruences| crt(congruences))
+        .map(|(step, modulus)| {
+            if step < longest_tail {
+                step + (longest_tail - step + modulus - 1) / modulus * modulus
+            } else {
+                step
             }
+        })
+        .min()
+        .ok_or_else(|| anyhow!("The ghosts never stand on goal nodes at the same time"))?;
+    Ok(u64::try_from(step)?)
+}
 
-            if let Some(end_node) = end_node {
-                break end_node;
-            }
-        };
+pub fn solve_task_one(input: Vec<String>) -> Result<Answer> {
+    let start_time = Instant::now();
+    let network = Network::from_lines(&input)?;
+    let cycle = network.ghost_cycle(START_NODE, |node| node == END_NODE)?;
+    let sol = first_common_step(&[cycle])?;
 
-        let (end_idx, end_node) = end_node;
-        let mut circle_len = 1;
-
-        let mut n = end_node;
-        let mut ldirs = dirs.iter().skip(end_idx);
-        let circle_nodes = loop {
-            if let Some(dir) = ldirs.next() {
-                match dir {
-                    'L' => n = &nodes[&n.left],
-                    'R' => n = &nodes[&n.right],
-                    _ => panic!(),
-                }
-                if end_node == n {
-                    break circle_len;
-                } else {
-                    circle_len += 1;
-                }
-            } else {
-                ldirs = dirs.iter().skip(0);
-            }
-        };
+    eprintln!("{:?}", Instant::now() - start_time);
+    Ok(sol.into())
+}
 
-        circle_nodes
-    });
+pub fn solve_task_two(input: Vec<String>) -> Result<Answer> {
+    let start_time = Instant::now();
+    let network = Network::from_lines(&input)?;
+    let cycles = network
+        .nodes()
+        .filter(|node| node.ends_with('A'))
+        .map(|start| network.ghost_cycle(start, |node| node.ends_with('Z')))
+        .collect::<Result<Vec<_>>>()?;
+    let sol = first_common_step(&cycles)?;
 
-    let lcm = circle_lens.into_iter().fold(1, lcm);
     eprintln!("Took {:?}", Instant::now() - start_time);
-    Ok(lcm)
+    Ok(sol.into())
 }
 
 #[cfg(test)]
@@ -191,7 +222,7 @@ mod test {
         Ok(lines?)
     }
 
-    use crate::{solve_task_one, solve_task_two};
+    use crate::{first_common_step, solve_task_one, solve_task_two, GhostCycle, Network};
 
     #[test]
     fn test_case_one_example_1() -> Result<()> {
@@ -233,4 +264,47 @@ mod test {
         assert_eq!(solve_task_two(file)?, 9064949303801);
         Ok(())
     }
+
+    #[test]
+    fn test_ghost_cycles() -> Result<()> {
+        let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let network =
+            Network::from_lines(&get_file(cargo_manifest_dir.join("inputs/example_3.txt"))?)?;
+        let cycle = network.ghost_cycle("22A", |node| node.ends_with('Z'))?;
+        assert_eq!(
+            cycle,
+            GhostCycle {
+                tail: 1,
+                period: 6,
+                tail_hits: vec![],
+                cycle_hits: vec![3, 6],
+            }
+        );
+        assert!(cycle.is_hit(9) && cycle.is_hit(12) && !cycle.is_hit(10));
+        Ok(())
+    }
+
+    #[test]
+    fn test_first_common_step_with_offsets() -> Result<()> {
+        let cycle = |tail, period, tail_hits: &[usize], cycle_hits: &[usize]| GhostCycle {
+            tail,
+            period,
+            tail_hits: tail_hits.to_vec(),
+            cycle_hits: cycle_hits.to_vec(),
+        };
+        // Hits on 3, 8, 13, ... and on 1, 5, 9, 13, ...
+        let ghosts = [cycle(3, 5, &[], &[3]), cycle(0, 4, &[], &[1])];
+        assert_eq!(first_common_step(&ghosts)?, 13);
+        // Common hit in a tail
+        let ghosts = [cycle(4, 5, &[2], &[4]), cycle(0, 2, &[], &[0])];
+        assert_eq!(first_common_step(&ghosts)?, 2);
+        // Several hits per cycle, pairing 4 with 7 would give 16
+        let ghosts = [cycle(0, 6, &[], &[1, 4]), cycle(0, 9, &[], &[7])];
+        assert_eq!(first_common_step(&ghosts)?, 7);
+        // Always on even and odd steps
+        let ghosts = [cycle(0, 2, &[], &[0]), cycle(0, 2, &[], &[1])];
+        assert!(first_common_step(&ghosts).is_err());
+        assert!(first_common_step(&[]).is_err());
+        Ok(())
+    }
 }
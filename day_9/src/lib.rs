@@ -1,101 +1,51 @@
 use std::time::Instant;
 
 use color_eyre::Result;
+use common::{
+    answer::{Answer, CheckedIterator},
+    sequence::Sequence,
+};
 
-fn get_historic_value(nums: Vec<i32>) -> i32 {
-    let mut differences = vec![nums];
-    let mut current: &Vec<i32> = differences.last_mut().unwrap();
-    loop {
-        let mut diffs = vec![];
-        let mut last_num = current.first().unwrap();
-        for n in current.into_iter().skip(1) {
-            diffs.push(*n - *last_num);
-            last_num = n;
-        }
-        differences.push(diffs);
-        current = differences.last_mut().unwrap();
-        if current.iter().all(|v| *v == 0) {
-            break;
-        }
-    }
-    let mut next_differences = differences.clone();
-    next_differences.last_mut().unwrap().insert(0, 0);
-    let mut prev = next_differences.pop().unwrap();
-    prev.reverse();
-
-    while let Some(mut cur) = next_differences.pop() {
-        cur.reverse();
-        let cur_last = *cur.last().unwrap();
-        let prev_last = prev.last_mut().unwrap();
-        cur.push(cur_last - *prev_last);
-
-        prev = cur;
-    }
-    *prev.last().unwrap()
+/// Fits the polynomial continuing a history. Like the puzzle, a difference
+/// row without values counts as all zeros, so short histories extrapolate too.
+pub fn fit_history(line: &str) -> Result<Sequence> {
+    let values = line
+        .split_ascii_whitespace()
+        .map(|e| e.parse::<i64>())
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Sequence::interpolate(&values)
 }
 
-fn get_next_value(nums: Vec<i32>) -> i32 {
-    let mut differences = vec![nums];
-    let mut current: &Vec<i32> = differences.last_mut().unwrap();
-    loop {
-        let mut diffs = vec![];
-        let mut last_num = current.first().unwrap();
-        for n in current.into_iter().skip(1) {
-            diffs.push(*n - *last_num);
-            last_num = n;
-        }
-        differences.push(diffs);
-        current = differences.last_mut().unwrap();
-        if current.iter().all(|v| *v == 0) {
-            break;
-        }
-    }
-    // differences.reverse();
-    let mut next_differences = differences.clone();
-    next_differences.last_mut().unwrap().push(0);
-    let mut prev = next_differences.pop().unwrap();
-    while let Some(mut cur) = next_differences.pop() {
-        let cur_last = *cur.last().unwrap();
-        let prev_last = prev.last_mut().unwrap();
-        cur.push(*prev_last - cur_last);
-        prev = cur;
-    }
-    *prev.last().unwrap()
+/// Sum of the values `k` steps after the end of every history, or before the
+/// start for negative `k`
+pub fn sum_extrapolated(input: &[String], k: i64) -> Result<i128> {
+    input
+        .iter()
+        .map(|line| {
+            let history = fit_history(line)?;
+            if k < 0 {
+                history.backward(-k)
+            } else {
+                history.forward(k)
+            }
+        })
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .try_sum()
 }
 
-pub fn solve_task_one(input: Vec<String>) -> Result<i32> {
+pub fn solve_task_one(input: Vec<String>) -> Result<Answer> {
     let start_time = Instant::now();
-    let sensor_value_histories = input
-        .into_iter()
-        .map(|s| {
-            s.split_ascii_whitespace()
-                .map(|e| e.parse::<i32>().unwrap())
-                .collect::<Vec<_>>()
-        })
-        .collect::<Vec<_>>();
-    // eprintln!("{:?}", sensor_value_histories);
-    let sol: i32 = sensor_value_histories.into_iter().map(get_next_value).sum();
+    let sol = sum_extrapolated(&input, 1)?;
     eprintln!("{:?}", Instant::now() - start_time);
-    Ok(-sol)
+    Ok(sol.into())
 }
 
-pub fn solve_task_two(input: Vec<String>) -> Result<i32> {
+pub fn solve_task_two(input: Vec<String>) -> Result<Answer> {
     let start_time = Instant::now();
-    let sensor_value_histories = input
-        .into_iter()
-        .map(|s| {
-            s.split_ascii_whitespace()
-                .map(|e| e.parse::<i32>().unwrap())
-                .collect::<Vec<_>>()
-        })
-        .collect::<Vec<_>>();
-    // eprintln!("{:?}", sensor_value_histories);
-    let sol = sensor_value_histories
-        .into_iter()
-        .map(get_historic_value)
-        .sum();
+    let sol = sum_extrapolated(&input, -1)?;
     eprintln!("{:?}", Instant::now() - start_time);
-    Ok(sol)
+    Ok(sol.into())
 }
 
 #[cfg(test)]
@@ -115,7 +65,7 @@ mod test {
         Ok(lines?)
     }
 
-    use crate::{solve_task_one, solve_task_two};
+    use crate::{fit_history, solve_task_one, solve_task_two, sum_extrapolated};
 
     #[test]
     fn test_case_one_example() -> Result<()> {
@@ -147,4 +97,21 @@ mod test {
         assert_eq!(solve_task_two(file)?, 948);
         Ok(())
     }
+
+    #[test]
+    fn test_extrapolate_offsets() -> Result<()> {
+        let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let file = get_file(cargo_manifest_dir.join("inputs/example_1.txt"))?;
+        // 0 3 6 9 12 15, 1 3 6 10 15 21 and 10 13 16 21 30 45
+        assert_eq!(sum_extrapolated(&file, 0)?, 15 + 21 + 45);
+        assert_eq!(sum_extrapolated(&file, 2)?, 21 + 36 + 101);
+        assert_eq!(sum_extrapolated(&file, -2)?, -6 - 4);
+        let history = fit_history(&file[2])?;
+        assert_eq!(history.polynomial().degree(), 3);
+        assert_eq!(fit_history("1 2 4 8")?.forward(1)?, 15);
+        assert_eq!(fit_history("3 5")?.forward(1)?, 7);
+        assert_eq!(fit_history("3 5")?.backward(1)?, 1);
+        assert_eq!(fit_history("4")?.forward(1)?, 4);
+        Ok(())
+    }
 }
@@ -1,14 +1,15 @@
 use std::time::Instant;
 
 use color_eyre::Result;
+use common::answer::Answer;
 
-pub fn solve_task_one(#[allow(unused_variables)] input: Vec<String>) -> Result<i32> {
+pub fn solve_task_one(#[allow(unused_variables)] input: Vec<String>) -> Result<Answer> {
     let start_time = Instant::now();
     eprintln!("{:?}", Instant::now() - start_time);
     todo!()
 }
 
-pub fn solve_task_two(#[allow(unused_variables)] input: Vec<String>) -> Result<i32> {
+pub fn solve_task_two(#[allow(unused_variables)] input: Vec<String>) -> Result<Answer> {
     let start_time = Instant::now();
     eprintln!("{:?}", Instant::now() - start_time);
     todo!()